@@ -0,0 +1,365 @@
+use super::{
+    AiAction, PlanContext, PlanTrace, SupportKind, SupportOption, SupportSide, TracedCandidate,
+    UnitView,
+};
+use crate::{
+    database::personality::{ActionBehaviour, MovementBehaviour},
+    game_entities::unit_data::UnitIdx,
+    pathfinding::RustPathfinder,
+};
+
+use godot::prelude::*;
+
+/// Action picked for a unit together with the cell it is performed from.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) struct ActionChoice {
+    pub(crate) cell: Vector2i,
+    pub(crate) action: AiAction,
+    pub(crate) target_idx: UnitIdx,
+    pub(crate) slot_idx: usize,
+}
+
+impl PlanContext<'_> {
+    /// Picks the action of the unit following its `ActionBehaviour`, trying
+    /// `cells` in order so the first one is used when several are as good.
+    /// Every candidate considered is recorded in `trace`.
+    ///
+    /// Returns `None` if the unit has nothing to do.
+    pub(crate) fn choose_action(
+        &self,
+        cells: &[(Vector2i, i32)],
+        trace: Option<&mut PlanTrace>,
+    ) -> Option<ActionChoice> {
+        match self.behaviour.action {
+            ActionBehaviour::DoNothing => None,
+            ActionBehaviour::AttackCloserEnemy
+            | ActionBehaviour::AttackWeakerEnemy
+            | ActionBehaviour::AttackMinimizingDamage => self.choose_attack(cells, trace),
+            ActionBehaviour::HealAllies
+            | ActionBehaviour::HealOneself
+            | ActionBehaviour::BuffAllies
+            | ActionBehaviour::BuffOneself
+            | ActionBehaviour::DebuffEnemies => self.choose_support(cells, trace),
+        }
+    }
+
+    /// Returns **true** if `to` lies within `unit_range` of `from`.
+    pub(crate) fn in_range(&self, from: Vector2i, to: Vector2i, unit_range: Vector2i) -> bool {
+        let distance = self.terrain_grid.topology.distance(from, to);
+        distance >= unit_range.x && distance <= unit_range.y
+    }
+
+    /// Returns **false** for hostile units a `Defend` unit ignores because
+    /// they are outside the aggro radius around its defend cell.
+    fn engages(&self, other: &UnitView) -> bool {
+        if !other.hostile || self.behaviour.movement != MovementBehaviour::Defend {
+            return true;
+        }
+
+        match (self.defend_cell, self.defend_radii.aggro_radius) {
+            (Some(defend), Some(aggro_radius)) => {
+                self.terrain_grid.topology.distance(other.cell, defend) <= aggro_radius as i32
+            }
+            _ => true,
+        }
+    }
+
+    /// Ranks `target` by the objectives of the unit, lower ranks are
+    /// attacked first:
+    /// 0. Units whose defeat makes their army lose the battle.
+    /// 1. Units that can reach a unit the unit protects this turn.
+    /// 2. Everyone else.
+    fn objective_rank(&self, target: &UnitView) -> i32 {
+        if self.objectives.critical_idxs.contains(&target.unit_idx) {
+            return 0;
+        }
+
+        let topology = self.terrain_grid.topology;
+        let threat_range = target.move_range + target.attack_range.y;
+
+        let threatens_protected = target.can_attack()
+            && self
+                .units
+                .iter()
+                .filter(|other| self.objectives.protected_idxs.contains(&other.unit_idx))
+                .any(|other| topology.distance(target.cell, other.cell) <= threat_range);
+
+        if threatens_protected { 1 } else { 2 }
+    }
+
+    /// Pseudo random amount up to the `target_jitter` of the difficulty added
+    /// to the score of `target_idx`. It only changes from one turn to the
+    /// next so plans can be reproduced.
+    fn target_jitter(&self, target_idx: UnitIdx) -> i32 {
+        let max_jitter = self.difficulty.target_jitter as u64;
+
+        if max_jitter == 0 {
+            return 0;
+        }
+
+        // SplitMix64 finalizer, spreads close inputs over the whole range.
+        let mut hash =
+            ((self.turn as u64) << 48) ^ ((self.unit.unit_idx as u64) << 24) ^ target_idx as u64;
+        hash = hash.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        hash = (hash ^ (hash >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        hash = (hash ^ (hash >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        hash ^= hash >> 31;
+
+        (hash % (max_jitter + 1)) as i32
+    }
+
+    /// Estimated damage the unit would receive from `target` in a counter
+    /// attack when attacking it from `cell`.
+    pub(crate) fn counter_damage(&self, target: &UnitView, cell: Vector2i) -> i32 {
+        if target.can_attack() && self.in_range(target.cell, cell, target.attack_range) {
+            let moved_unit = UnitView {
+                cell,
+                ..self.unit.clone()
+            };
+
+            target.estimated_damage(&moved_unit, self.terrain_grid, &self.rules)
+        } else {
+            0
+        }
+    }
+
+    fn choose_attack(
+        &self,
+        cells: &[(Vector2i, i32)],
+        mut trace: Option<&mut PlanTrace>,
+    ) -> Option<ActionChoice> {
+        let weapon_slot = self.weapon_slot.filter(|_| self.unit.can_attack())?;
+        let topology = self.terrain_grid.topology;
+
+        self.units
+            .iter()
+            .filter(|target| target.hostile && self.engages(target))
+            .filter_map(|target| {
+                let mut attack_cells = cells
+                    .iter()
+                    .filter(|(cell, _)| self.in_range(*cell, target.cell, self.unit.attack_range))
+                    .map(|(cell, _)| (*cell, self.counter_damage(target, *cell)))
+                    .filter(|(_, counter_damage)| {
+                        self.difficulty.allow_suicide || *counter_damage < self.unit.current_htp
+                    });
+
+                let (cell, counter_damage) = match self.behaviour.action {
+                    ActionBehaviour::AttackMinimizingDamage => {
+                        attack_cells.min_by_key(|(_, counter_damage)| *counter_damage)?
+                    }
+                    _ => attack_cells.next()?,
+                };
+
+                let damage = self
+                    .unit
+                    .estimated_damage(target, self.terrain_grid, &self.rules);
+
+                let (first_key, second_key) = match self.behaviour.action {
+                    ActionBehaviour::AttackCloserEnemy => {
+                        (topology.distance(self.unit.cell, target.cell), 0)
+                    }
+                    ActionBehaviour::AttackWeakerEnemy => (-damage, target.current_htp),
+                    _ => (counter_damage, -damage),
+                };
+                let target_key = (
+                    self.objective_rank(target),
+                    first_key + self.target_jitter(target.unit_idx),
+                    second_key,
+                );
+
+                if let Some(trace) = trace.as_deref_mut() {
+                    trace.candidates.push(TracedCandidate {
+                        action: AiAction::Attack,
+                        target_idx: target.unit_idx,
+                        cell,
+                        slot_idx: weapon_slot,
+                        score: vec![target_key.0, target_key.1, target_key.2],
+                    });
+                }
+
+                Some((target_key, target.unit_idx, cell))
+            })
+            .min_by_key(|(target_key, target_idx, _)| (*target_key, *target_idx))
+            .map(|(_, target_idx, cell)| ActionChoice {
+                cell,
+                action: AiAction::Attack,
+                target_idx,
+                slot_idx: weapon_slot,
+            })
+    }
+
+    fn choose_support(
+        &self,
+        cells: &[(Vector2i, i32)],
+        mut trace: Option<&mut PlanTrace>,
+    ) -> Option<ActionChoice> {
+        let action = self.behaviour.action;
+        let oneself = matches!(
+            action,
+            ActionBehaviour::HealOneself | ActionBehaviour::BuffOneself
+        );
+
+        let mut best_choice = None::<((i32, i32, bool), ActionChoice)>;
+
+        for option in self.support_options.iter().filter(|option| {
+            matches!(
+                (action, option.kind),
+                (
+                    ActionBehaviour::HealAllies | ActionBehaviour::HealOneself,
+                    SupportKind::Heal(_)
+                ) | (
+                    ActionBehaviour::BuffAllies | ActionBehaviour::BuffOneself,
+                    SupportKind::Buff
+                ) | (ActionBehaviour::DebuffEnemies, SupportKind::Debuff)
+            )
+        }) {
+            for (cell, _) in cells {
+                let moved_unit = UnitView {
+                    cell: *cell,
+                    ..self.unit.clone()
+                };
+
+                for (target_idx, affected) in self.support_targets(option, &moved_unit) {
+                    let includes_self = affected
+                        .iter()
+                        .any(|unit| unit.unit_idx == moved_unit.unit_idx);
+
+                    if oneself != includes_self {
+                        continue;
+                    }
+
+                    // Consumables are kept for when equipment brings less benefit.
+                    let Some((benefit_key, tie_key)) = Self::support_key(option.kind, &affected)
+                    else {
+                        continue;
+                    };
+                    let choice_key = (benefit_key, tie_key, option.consumable);
+                    let choice = ActionChoice {
+                        cell: *cell,
+                        action: match option.kind {
+                            SupportKind::Heal(_) => AiAction::Heal,
+                            SupportKind::Buff => AiAction::Buff,
+                            SupportKind::Debuff => AiAction::Debuff,
+                        },
+                        target_idx,
+                        slot_idx: option.slot_idx,
+                    };
+
+                    if let Some(trace) = trace.as_deref_mut() {
+                        trace.candidates.push(TracedCandidate {
+                            action: choice.action,
+                            target_idx,
+                            cell: *cell,
+                            slot_idx: option.slot_idx,
+                            score: vec![benefit_key, tie_key, option.consumable as i32],
+                        });
+                    }
+
+                    if best_choice
+                        .as_ref()
+                        .is_none_or(|(best_key, _)| choice_key < *best_key)
+                    {
+                        best_choice = Some((choice_key, choice));
+                    }
+                }
+            }
+        }
+
+        best_choice.map(|(_, choice)| choice)
+    }
+
+    /// Ranks the use of an effect of `kind` on the `affected` units, lower
+    /// keys are better. Heals favour the most wounded unit and then the total
+    /// amount healed, buffs and debuffs the number of units affected.
+    ///
+    /// Returns `None` if using the effect brings no benefit.
+    fn support_key(kind: SupportKind, affected: &[&UnitView]) -> Option<(i32, i32)> {
+        let side_sign = |unit: &UnitView| if unit.hostile { -1 } else { 1 };
+
+        match kind {
+            SupportKind::Heal(power) => {
+                let healed = affected
+                    .iter()
+                    .map(|unit| side_sign(unit) * power.min(unit.missing_htp()))
+                    .sum::<i32>();
+                let lowest_percent = affected
+                    .iter()
+                    .filter(|unit| !unit.hostile && unit.missing_htp() > 0)
+                    .map(|unit| unit.htp_percent())
+                    .min()?;
+
+                (healed > 0).then_some((lowest_percent, -healed))
+            }
+            SupportKind::Buff | SupportKind::Debuff => {
+                let sign = if kind == SupportKind::Buff { 1 } else { -1 };
+                let benefit = affected
+                    .iter()
+                    .map(|unit| sign * side_sign(unit))
+                    .sum::<i32>();
+
+                (benefit > 0).then_some((-benefit, 0))
+            }
+        }
+    }
+
+    /// Lists the units `option` can be aimed at when used by `unit`, together
+    /// with every unit affected by each use.
+    fn support_targets<'v>(
+        &'v self,
+        option: &SupportOption,
+        unit: &'v UnitView,
+    ) -> Vec<(UnitIdx, Vec<&'v UnitView>)> {
+        if option.side == SupportSide::Oneself {
+            return vec![(unit.unit_idx, vec![unit])];
+        }
+
+        let everyone =
+            || std::iter::once((unit, true)).chain(self.units.iter().map(|other| (other, false)));
+
+        let aimed_units = everyone()
+            .filter(|(other, is_self)| {
+                option.can_affect(other, *is_self)
+                    && self.engages(other)
+                    && self.in_range(unit.cell, other.cell, option.range)
+            })
+            .map(|(other, _)| other)
+            .collect::<Vec<_>>();
+
+        if let Some(area) = option.area {
+            aimed_units
+                .iter()
+                .map(|aimed_unit| {
+                    // Support options are always aimed at a unit.
+                    let area_cells = RustPathfinder::effect_area_with(
+                        area,
+                        unit.cell,
+                        aimed_unit.cell,
+                        true,
+                        self.terrain_grid.grid_bounds,
+                        self.terrain_grid.topology,
+                    );
+
+                    let affected = everyone()
+                        .filter(|(other, is_self)| {
+                            option.can_affect(other, *is_self) && area_cells.contains(&other.cell)
+                        })
+                        .map(|(other, _)| other)
+                        .collect();
+
+                    (aimed_unit.unit_idx, affected)
+                })
+                .collect()
+        } else if option.every_target {
+            aimed_units
+                .first()
+                .map(|aimed_unit| (aimed_unit.unit_idx, aimed_units.clone()))
+                .into_iter()
+                .collect()
+        } else {
+            aimed_units
+                .into_iter()
+                .map(|aimed_unit| (aimed_unit.unit_idx, vec![aimed_unit]))
+                .collect()
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use super::UnitView;
+use crate::{
+    database::{
+        difficulty::DifficultyProfile,
+        personality::{BehaviourKey, BehaviourThresholds, PersonalityEntry, UnitBehaviour},
+    },
+    pathfinding::GridTopology,
+};
+
+/// Returns the `BehaviourKey`s active for `unit` given the other placed
+/// `units` and the `thresholds` of its personality, in `BehaviourKey` order.
+///
+/// `LowValor` is only evaluated when the unit's `valor` is known.
+pub(crate) fn active_keys(
+    unit: &UnitView,
+    units: &[UnitView],
+    valor: Option<i32>,
+    thresholds: &BehaviourThresholds,
+    topology: GridTopology,
+) -> Vec<BehaviourKey> {
+    let mut keys = Vec::new();
+
+    if unit.htp_percent() <= thresholds.low_htp_percent as i32 {
+        keys.push(BehaviourKey::LowHtp);
+    }
+
+    if valor.is_some_and(|valor| valor <= thresholds.low_valor as i32) {
+        keys.push(BehaviourKey::LowValor);
+    }
+
+    let radius = if thresholds.close_radius > 0 {
+        thresholds.close_radius as i32
+    } else {
+        unit.move_range + 1
+    };
+
+    let (enemies, allies) = units
+        .iter()
+        .filter(|other| topology.distance(unit.cell, other.cell) <= radius)
+        .fold((0, 0), |(enemies, allies), other| {
+            if other.hostile {
+                (enemies + 1, allies)
+            } else {
+                (enemies, allies + 1)
+            }
+        });
+
+    if enemies > allies {
+        keys.push(BehaviourKey::EnemiesClose);
+    } else if allies > enemies {
+        keys.push(BehaviourKey::AlliesClose);
+    }
+
+    keys
+}
+
+/// Adjusts the `thresholds` of a personality to the `difficulty` profile.
+pub(crate) fn difficulty_thresholds(
+    thresholds: &BehaviourThresholds,
+    difficulty: &DifficultyProfile,
+) -> BehaviourThresholds {
+    let low_htp_percent =
+        thresholds.low_htp_percent as u32 * difficulty.caution_percent as u32 / 100;
+
+    BehaviourThresholds {
+        low_htp_percent: low_htp_percent.min(100) as u8,
+        ..*thresholds
+    }
+}
+
+/// Picks the conditional behaviour of `personality` with the highest
+/// priority among the `active_keys`, or its default behaviour if none of
+/// them has one.
+pub(crate) fn select_behaviour(
+    personality: &PersonalityEntry,
+    active_keys: &[BehaviourKey],
+) -> UnitBehaviour {
+    active_keys
+        .iter()
+        .filter_map(|key| personality.conditional.get(key))
+        .max_by_key(|behaviour| behaviour.priority)
+        .copied()
+        .unwrap_or(personality.default)
+}
@@ -0,0 +1,389 @@
+use super::{
+    BattleObjectives, PhaseCache, SupportKind, SupportOption, SupportSide, UnitObjectives,
+    UnitView,
+    behaviour::{active_keys, difficulty_thresholds, select_behaviour},
+};
+use crate::{
+    database::{
+        DbConnector,
+        chapter::DefendRadii,
+        combat_rules::CombatRules,
+        difficulty::DifficultyProfile,
+        effect::{EffectId, EffectVariant, HealthEffect, HealthTarget},
+        inventory::{EffectTarget, EntryVariant, ItemEntry, WeaponSlotCategory},
+        personality::{BehaviourKey, MovementBehaviour, UnitBehaviour},
+    },
+    game_entities::{
+        army_states::ArmyStates,
+        unit_data::{UnitData, UnitIdx},
+        unit_states::UnitStates,
+    },
+    pathfinding::{RustPathfinder, TerrainGrid},
+};
+
+use godot::prelude::*;
+use std::collections::{HashMap, HashSet};
+
+/// Battle state the planner takes its decisions from.
+pub(crate) struct BattleView<'a> {
+    pub(crate) db: &'a DbConnector,
+    pub(crate) unit_states: &'a UnitStates,
+    pub(crate) army_states: &'a ArmyStates,
+    pub(crate) terrain_grid: &'a TerrainGrid,
+    pub(crate) zone_of_control: bool,
+    /// Current valor of the units, used to evaluate `BehaviourKey::LowValor`
+    pub(crate) unit_valor: &'a HashMap<UnitIdx, i32>,
+    /// Hit points overriding the current ones of the units, used to take
+    /// into account the outcome of actions planned earlier in the phase
+    pub(crate) unit_htp: &'a HashMap<UnitIdx, i32>,
+    /// Objectives of the battle, if the planner knows them
+    pub(crate) objectives: Option<&'a BattleObjectives>,
+    pub(crate) difficulty: DifficultyProfile,
+    /// Combat rules followed when estimating the damage of attacks
+    pub(crate) rules: CombatRules,
+    /// Flow fields and danger zones shared with the other units of the phase
+    pub(crate) phase_cache: Option<&'a PhaseCache>,
+}
+
+/// Everything the planner needs to decide the turn of a single unit.
+pub(crate) struct PlanContext<'a> {
+    pub(crate) unit: UnitView,
+    /// Every other placed unit that is either friendly or hostile to `unit`
+    pub(crate) units: Vec<UnitView>,
+    pub(crate) active_keys: Vec<BehaviourKey>,
+    pub(crate) behaviour: UnitBehaviour,
+    pub(crate) weapon_slot: Option<usize>,
+    pub(crate) support_options: Vec<SupportOption>,
+    /// Cells the unit can end its movement on together with their cost
+    pub(crate) move_costs: HashMap<Vector2i, i32>,
+    /// Movement cost table of the unit's movement class
+    pub(crate) costs: &'a [i32],
+    pub(crate) defend_cell: Option<Vector2i>,
+    pub(crate) defend_radii: DefendRadii,
+    /// Cells hostile units can attack next turn, only computed for
+    /// `MovementBehaviour::Tactician`
+    pub(crate) danger_zone: HashSet<Vector2i>,
+    /// Objectives of the battle for the unit, only resolved for
+    /// `MovementBehaviour::Tactician`
+    pub(crate) objectives: UnitObjectives,
+    pub(crate) difficulty: DifficultyProfile,
+    pub(crate) rules: CombatRules,
+    /// Current turn of the battle, used to vary the random choices of the
+    /// unit from one turn to the next
+    pub(crate) turn: u16,
+    pub(crate) terrain_grid: &'a TerrainGrid,
+    /// Flow fields shared with the other units of the phase
+    pub(crate) phase_cache: Option<&'a PhaseCache>,
+}
+
+/// Situation of a unit on the map and the behaviour of its personality
+/// that applies to it.
+pub(crate) struct UnitAssessment {
+    pub(crate) unit: UnitView,
+    pub(crate) weapon_slot: Option<usize>,
+    /// Every other placed unit that is either friendly or hostile to `unit`
+    pub(crate) units: Vec<UnitView>,
+    pub(crate) active_keys: Vec<BehaviourKey>,
+    pub(crate) behaviour: UnitBehaviour,
+}
+
+impl UnitAssessment {
+    /// Assesses the situation of `unit_idx` in the `battle` state.
+    ///
+    /// Returns `None` if the unit is not placed in the map, does not belong
+    /// to any army or its personality cannot be found.
+    pub(crate) fn for_unit(unit_idx: UnitIdx, battle: &BattleView) -> Option<Self> {
+        let BattleView {
+            db,
+            unit_states,
+            army_states,
+            ..
+        } = *battle;
+
+        let (Some(origin), Some(unit_data), Some(army_id)) = (
+            unit_states.unit_idx_to_cell.get(&unit_idx).copied(),
+            unit_states.data_store.get(&unit_idx),
+            unit_states.unit_idx_to_army_id.get(&unit_idx),
+        ) else {
+            godot_error!("Unit [{}] is not placed in the map!", unit_idx);
+            return None;
+        };
+
+        let Some(personality) = unit_states
+            .unit_personalities
+            .get(&unit_idx)
+            .and_then(|personality_id| db.personalities.get(personality_id))
+        else {
+            godot_error!("Personality of unit [{}] not found!", unit_idx);
+            return None;
+        };
+
+        let (unit, weapon_slot) = Self::view_of(unit_idx, origin, false, &unit_data.bind(), battle);
+
+        let mut units = unit_states
+            .unit_idx_to_cell
+            .iter()
+            .filter(|(other_idx, _)| **other_idx != unit_idx)
+            .filter_map(|(other_idx, other_cell)| {
+                let other_army_id = unit_states.unit_idx_to_army_id.get(other_idx)?;
+                let hostile = army_states.are_hostile(army_id, other_army_id);
+
+                if !hostile && !army_states.are_friendly(army_id, other_army_id) {
+                    return None;
+                }
+
+                let other_data = unit_states.data_store.get(other_idx)?.bind();
+
+                Some(Self::view_of(*other_idx, *other_cell, hostile, &other_data, battle).0)
+            })
+            .collect::<Vec<_>>();
+
+        units.sort_unstable_by_key(|other| other.unit_idx);
+
+        let thresholds = difficulty_thresholds(&personality.thresholds, &battle.difficulty);
+
+        let active_keys = active_keys(
+            &unit,
+            &units,
+            battle.unit_valor.get(&unit_idx).copied(),
+            &thresholds,
+            battle.terrain_grid.topology,
+        );
+        let behaviour = select_behaviour(personality, &active_keys);
+
+        Some(Self {
+            unit,
+            weapon_slot,
+            units,
+            active_keys,
+            behaviour,
+        })
+    }
+
+    /// Builds the view of a unit, together with the slot of the weapon used
+    /// for its attack stats, the equipped one if any or the first one found.
+    fn view_of(
+        unit_idx: UnitIdx,
+        cell: Vector2i,
+        hostile: bool,
+        unit_data: &UnitData,
+        battle: &BattleView,
+    ) -> (UnitView, Option<usize>) {
+        let db = battle.db;
+
+        let mut unit_view = UnitView {
+            unit_idx,
+            cell,
+            hostile,
+            current_htp: battle
+                .unit_htp
+                .get(&unit_idx)
+                .copied()
+                .unwrap_or(unit_data.current_htp as i32),
+            max_htp: unit_data.get_current_max_htp() as i32,
+            move_range: unit_data.get_current_mov() as i32,
+            attack_range: unit_data.attack_range,
+            def: unit_data.get_current_def() as i32,
+            spt: unit_data.get_current_spt() as i32,
+            ..Default::default()
+        };
+
+        let equipped_slot = usize::try_from(unit_data.equipped_slot_idx).ok();
+
+        let weapon = equipped_slot
+            .into_iter()
+            .chain(0..unit_data.inventory_slots.len())
+            .find_map(|slot_idx| {
+                let slot = unit_data.inventory_slots.get(slot_idx)?;
+                let entry = slot.get_entry().filter(|_| slot.contains_weapon())?;
+
+                match &db.inventory.get(&entry.id)?._variant {
+                    EntryVariant::Weapon(weapon_entry) => Some((slot_idx, weapon_entry)),
+                    _ => None,
+                }
+            });
+
+        let weapon_slot = weapon.map(|(slot_idx, weapon_entry)| {
+            let scaling_stat = match weapon_entry.slot_category {
+                WeaponSlotCategory::Physical => unit_data.get_current_str(),
+                WeaponSlotCategory::Magical => unit_data.get_current_mag(),
+            };
+
+            unit_view.attack_power = weapon_entry.power as i32 + scaling_stat as i32;
+            unit_view.damage_type = Some(weapon_entry.damage_type);
+            unit_view.weapon_color = Some(weapon_entry.color);
+
+            slot_idx
+        });
+
+        (unit_view, weapon_slot)
+    }
+}
+
+impl<'a> PlanContext<'a> {
+    /// Gathers the context of `unit_idx` from the `battle` state.
+    ///
+    /// Returns `None` if the unit cannot be assessed or its movement class
+    /// is not present in the terrain grid.
+    pub(crate) fn for_unit(unit_idx: UnitIdx, battle: &BattleView<'a>) -> Option<Self> {
+        let BattleView {
+            db,
+            unit_states,
+            army_states,
+            terrain_grid,
+            zone_of_control,
+            ..
+        } = *battle;
+
+        let assessment = UnitAssessment::for_unit(unit_idx, battle)?;
+
+        let move_costs = RustPathfinder::placed_unit_movement_costs(
+            unit_idx,
+            unit_states,
+            army_states,
+            terrain_grid,
+            zone_of_control,
+        )?;
+
+        let unit_data = unit_states.data_store.get(&unit_idx)?.bind();
+        let costs = terrain_grid.costs_for(&unit_data.movement_class)?;
+        let mut support_options = Self::support_options_of(&unit_data, db);
+        support_options.retain(|option| battle.difficulty.use_consumables || !option.consumable);
+
+        let tactician = assessment.behaviour.movement == MovementBehaviour::Tactician;
+
+        let danger_zone = if tactician {
+            let army_id = unit_states.unit_idx_to_army_id.get(&unit_idx)?;
+
+            let danger_zone = || {
+                unit_states
+                    .army_units
+                    .keys()
+                    .filter(|other_army_id| army_states.are_hostile(army_id, other_army_id))
+                    .flat_map(|other_army_id| {
+                        RustPathfinder::danger_zone_of(
+                            other_army_id,
+                            unit_states,
+                            army_states,
+                            terrain_grid,
+                            zone_of_control,
+                        )
+                    })
+                    .collect()
+            };
+
+            match battle.phase_cache {
+                Some(phase_cache) => phase_cache.danger_zone(army_id, danger_zone),
+                None => danger_zone(),
+            }
+        } else {
+            HashSet::new()
+        };
+
+        let objectives = battle
+            .objectives
+            .filter(|_| tactician)
+            .map(|objectives| objectives.for_unit(unit_idx, battle))
+            .unwrap_or_default();
+
+        Some(Self {
+            unit: assessment.unit,
+            units: assessment.units,
+            active_keys: assessment.active_keys,
+            behaviour: assessment.behaviour,
+            weapon_slot: assessment.weapon_slot,
+            support_options,
+            move_costs,
+            costs,
+            defend_cell: unit_states.unit_defend_cells.get(&unit_idx).copied(),
+            defend_radii: unit_states
+                .unit_defend_radii
+                .get(&unit_idx)
+                .copied()
+                .unwrap_or_default(),
+            danger_zone,
+            objectives,
+            difficulty: battle.difficulty,
+            rules: battle.rules,
+            turn: army_states.current_turn,
+            terrain_grid,
+            phase_cache: battle.phase_cache,
+        })
+    }
+
+    /// Collects the support equipment and consumables of `unit_data` whose
+    /// effect the planner knows how to use.
+    fn support_options_of(unit_data: &UnitData, db: &DbConnector) -> Vec<SupportOption> {
+        unit_data
+            .inventory_slots
+            .iter()
+            .enumerate()
+            .filter_map(|(slot_idx, slot)| {
+                let entry = slot.get_entry()?;
+
+                let (effect_id, effect_target, area, consumable) =
+                    match &db.inventory.get(&entry.id)?._variant {
+                        EntryVariant::Support(support_entry) if slot.contains_support() => (
+                            &support_entry.effect_id,
+                            support_entry.effect_target,
+                            support_entry.area,
+                            false,
+                        ),
+                        EntryVariant::Item(ItemEntry::Consumable(consumable_entry)) => (
+                            &consumable_entry.effect_id,
+                            consumable_entry.effect_target,
+                            consumable_entry.area,
+                            true,
+                        ),
+                        _ => return None,
+                    };
+
+                let (side, range, every_target) = match effect_target {
+                    EffectTarget::Oneself => (SupportSide::Oneself, Vector2i::ZERO, false),
+                    EffectTarget::Ally(range) => (SupportSide::Friendly, range.to_godot(), false),
+                    EffectTarget::Enemy(range) => (SupportSide::Hostile, range.to_godot(), false),
+                    EffectTarget::Allies(range) => (SupportSide::Friendly, range.to_godot(), true),
+                    EffectTarget::Enemies(range) => (SupportSide::Hostile, range.to_godot(), true),
+                    EffectTarget::All(range) => (SupportSide::Any, range.to_godot(), true),
+                };
+
+                Some(SupportOption {
+                    slot_idx,
+                    kind: Self::support_kind(effect_id, db)?,
+                    side,
+                    range,
+                    every_target,
+                    area,
+                    consumable,
+                })
+            })
+            .collect()
+    }
+
+    /// Classifies the effect `effect_id`, parent effects take the kind of
+    /// their first child effect with a known kind.
+    fn support_kind(effect_id: &EffectId, db: &DbConnector) -> Option<SupportKind> {
+        match &db.effects.get(effect_id)?.variant {
+            EffectVariant::Parent(child_effects) => child_effects
+                .iter()
+                .find_map(|child_effect| Self::support_kind(child_effect, db)),
+            EffectVariant::Health(HealthEffect {
+                target: HealthTarget::Htp,
+                power,
+            }) if *power > 0 => Some(SupportKind::Heal(*power as i32)),
+            EffectVariant::StatModifier(stat_effect) => Some(if stat_effect.amount > 0 {
+                SupportKind::Buff
+            } else {
+                SupportKind::Debuff
+            }),
+            EffectVariant::CombatStatModifier(combat_stat_effect) => {
+                Some(if combat_stat_effect.amount > 0 {
+                    SupportKind::Buff
+                } else {
+                    SupportKind::Debuff
+                })
+            }
+            _ => None,
+        }
+    }
+}
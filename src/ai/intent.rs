@@ -0,0 +1,80 @@
+use super::UnitPlan;
+use crate::game_entities::{unit_data::UnitIdx, unit_states::UnitStates};
+
+use godot::prelude::*;
+use std::collections::HashMap;
+
+/// Placement and hit points of every placed unit, plans stay valid as long
+/// as neither changes.
+#[derive(Clone, Default, PartialEq, Eq, Debug)]
+pub(crate) struct BattleSnapshot {
+    unit_cells: HashMap<UnitIdx, Vector2i>,
+    unit_htp: HashMap<UnitIdx, u8>,
+    turn: u16,
+}
+
+impl BattleSnapshot {
+    pub(crate) fn of(unit_states: &UnitStates, turn: u16) -> Self {
+        Self {
+            unit_cells: unit_states.unit_idx_to_cell.clone(),
+            unit_htp: unit_states
+                .unit_idx_to_cell
+                .keys()
+                .filter_map(|unit_idx| {
+                    let unit_data = unit_states.data_store.get(unit_idx)?;
+
+                    Some((*unit_idx, unit_data.bind().current_htp))
+                })
+                .collect(),
+            turn,
+        }
+    }
+}
+
+/// Plans of the next phase of an army, kept until the battle changes.
+pub(crate) struct IntentCache {
+    pub(crate) snapshot: BattleSnapshot,
+    pub(crate) plans: Vec<UnitPlan>,
+}
+
+impl IntentCache {
+    /// Cell each planned action is aimed at, taking into account the moves
+    /// of the units acting before. `None` for units that only move.
+    pub(crate) fn target_cells(&self) -> Vec<Option<Vector2i>> {
+        let mut unit_cells = self.snapshot.unit_cells.clone();
+
+        self.plans
+            .iter()
+            .map(|unit_plan| {
+                unit_cells.insert(unit_plan.unit_idx, unit_plan.move_to);
+
+                unit_plan
+                    .target_idx
+                    .and_then(|target_idx| unit_cells.get(&target_idx))
+                    .copied()
+            })
+            .collect()
+    }
+
+    /// Intents of the units of the cached plans keyed by unit_idx, as
+    /// returned by `AiPlanner::get_planned_intents`.
+    pub(crate) fn to_intents(&self) -> Dictionary {
+        self.plans
+            .iter()
+            .zip(self.target_cells())
+            .enumerate()
+            .map(|(order, (unit_plan, target_cell))| {
+                (
+                    unit_plan.unit_idx,
+                    dict! {
+                        "order": order as i64,
+                        "move_to": unit_plan.move_to,
+                        "action": unit_plan.action as u8,
+                        "target_idx": unit_plan.target_idx.map_or(-1, i64::from),
+                        "target_cell": target_cell.unwrap_or(Vector2i::new(-1, -1)),
+                    },
+                )
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,1212 @@
+use crate::{
+    database::{
+        DbConnector,
+        army::ArmyId,
+        chapter::{ChapterKey, ChapterSegment},
+        combat_rules::{CombatRules, CombatRulesId},
+        difficulty::{DifficultyId, DifficultyProfile},
+    },
+    game_entities::{army_states::ArmyStates, unit_data::UnitIdx, unit_states::UnitStates},
+    pathfinding::TerrainGrid,
+};
+
+use godot::prelude::*;
+use std::collections::HashMap;
+
+mod actions;
+mod behaviour;
+mod context;
+mod intent;
+mod movement;
+mod objective;
+mod phase;
+mod plan;
+mod trace;
+mod unit_view;
+
+pub(crate) use context::*;
+pub(crate) use intent::*;
+pub(crate) use objective::*;
+pub(crate) use phase::*;
+pub(crate) use plan::*;
+pub(crate) use trace::*;
+pub(crate) use unit_view::*;
+
+/// Decides the turn of units controlled by the AI following the behaviours
+/// of their personality.
+#[derive(GodotClass)]
+#[class(no_init, base=RefCounted)]
+pub(crate) struct AiPlanner {
+    db: Gd<DbConnector>,
+    terrain_grid: Gd<TerrainGrid>,
+    zone_of_control: bool,
+    /// Current valor of the units, used to evaluate `BehaviourKey::LowValor`
+    unit_valor: HashMap<UnitIdx, i32>,
+    /// Whether plans include a trace of how they were decided
+    tracing: bool,
+    /// Objectives of the current battle followed by `Tactician` units
+    objectives: Option<BattleObjectives>,
+    difficulty: DifficultyProfile,
+    /// Combat rules the damage of attacks is estimated with, the same ones
+    /// given to `CombatCalculator::set_rules`
+    rules: CombatRules,
+    /// Plans of the next phase of each army returned by `get_planned_intents`
+    intents: HashMap<ArmyId, IntentCache>,
+}
+
+#[godot_api]
+impl AiPlanner {
+    /// Creates a planner for the map of `terrain_grid`, movement follows the
+    /// same rules as in `RustPathfinder::compute_unit_movement_range`.
+    #[func]
+    fn with_grid(
+        db: Gd<DbConnector>,
+        terrain_grid: Gd<TerrainGrid>,
+        zone_of_control: bool,
+    ) -> Gd<Self> {
+        Gd::from_object(Self {
+            db,
+            terrain_grid,
+            zone_of_control,
+            unit_valor: HashMap::new(),
+            tracing: false,
+            objectives: None,
+            difficulty: DifficultyProfile::default(),
+            rules: CombatRules::default(),
+            intents: HashMap::new(),
+        })
+    }
+
+    /// Applies the difficulty profile `difficulty_id` to the decisions of
+    /// every unit, an empty identifier restores the default profile.
+    ///
+    /// Returns **false** if the difficulty could not be found.
+    #[func]
+    fn set_difficulty(&mut self, difficulty_id: DifficultyId) -> bool {
+        self.intents.clear();
+
+        if difficulty_id.is_empty() {
+            self.difficulty = DifficultyProfile::default();
+            return true;
+        }
+
+        let Some(difficulty) = self
+            .db
+            .bind()
+            .difficulties
+            .get(&difficulty_id)
+            .map(|entry| entry.profile)
+        else {
+            godot_error!("Difficulty [{}] not found in database!", difficulty_id);
+            return false;
+        };
+
+        self.difficulty = difficulty;
+
+        true
+    }
+
+    /// Estimates the damage of attacks following the combat rules
+    /// `combat_rules_id`, which should match the ones of the
+    /// `CombatCalculator` resolving the combats. An empty identifier
+    /// restores the default rules.
+    ///
+    /// Returns **false** if the combat rules could not be found.
+    #[func]
+    fn set_rules(&mut self, combat_rules_id: CombatRulesId) -> bool {
+        self.intents.clear();
+
+        if combat_rules_id.is_empty() {
+            self.rules = CombatRules::default();
+            return true;
+        }
+
+        let Some(rules) = self
+            .db
+            .bind()
+            .combat_rules
+            .get(&combat_rules_id)
+            .map(|entry| entry.rules)
+        else {
+            godot_error!("Combat rules [{}] not found in database!", combat_rules_id);
+            return false;
+        };
+
+        self.rules = rules;
+
+        true
+    }
+
+    /// Loads the objectives of the battle segment `segment_idx` of
+    /// `chapter_key`. Units hostile to the player army following
+    /// `MovementBehaviour::Tactician` then:
+    /// * Occupy the cells the player army has to reach or defend.
+    /// * Stay close to the units the player army has to defeat and attack
+    ///   first the hostile units that threaten them.
+    /// * Attack first the player units whose defeat makes it lose the battle.
+    ///
+    /// Returns **false** if the segment is not a battle.
+    #[func]
+    fn load_objectives(&mut self, chapter_key: ChapterKey, segment_idx: u32) -> bool {
+        let db = self.db.bind();
+
+        let Some(segment) = db
+            .chapters
+            .get(&chapter_key)
+            .and_then(|chapter| chapter.segments.get(segment_idx as usize))
+        else {
+            godot_error!(
+                "Could not find segment [{}] of chapter [{}]!",
+                segment_idx,
+                chapter_key
+            );
+            return false;
+        };
+
+        let ChapterSegment::Battle(config) = segment else {
+            godot_error!(
+                "Segment [{}] of chapter [{}] is not a battle!",
+                segment_idx,
+                chapter_key
+            );
+            return false;
+        };
+
+        let objectives =
+            BattleObjectives::from_conditions(&config.victory_condition, &config.defeat_condition);
+        drop(db);
+
+        self.objectives = Some(objectives);
+        self.intents.clear();
+
+        true
+    }
+
+    #[func]
+    fn clear_objectives(&mut self) {
+        self.objectives = None;
+        self.intents.clear();
+    }
+
+    /// Enables or disables attaching a `trace` dictionary to the returned
+    /// plans, describing how the AI reached its decision:
+    ///```
+    /// {
+    ///     active_keys: [<behaviour_key>, ...],
+    ///     behaviour: { priority: <priority>, movement: <movement_behaviour>, action: <action_behaviour> },
+    ///     cells: [{ cell: <cell>, cost: <path_cost> }, ...],
+    ///     candidates: [{ action: <action>, target_idx: <target_unit_idx>, cell: <cell>, slot_idx: <inventory_slot_idx>, score: [<score>, ...] }, ...],
+    ///     movement: [{ cell: <cell>, score: [<score>, ...] }, ...],
+    ///     phase_rank: [<action_rank>, <tie_rank>],
+    /// }
+    ///```
+    /// `cells` are the cells the unit could act from, preferred first. Scores
+    /// are compared in order and the lowest one is picked. `movement` is only
+    /// filled when the unit had no action to perform and `phase_rank` is only
+    /// present in plans returned by `plan_army_phase`.
+    #[func]
+    fn set_tracing(&mut self, enabled: bool) {
+        self.tracing = enabled;
+    }
+
+    #[func]
+    fn is_tracing(&self) -> bool {
+        self.tracing
+    }
+
+    /// Sets the current valor of `unit_idx`, units without a valor set never
+    /// have `BehaviourKey::LowValor` active.
+    #[func]
+    fn set_unit_valor(&mut self, unit_idx: UnitIdx, valor: i32) {
+        self.unit_valor.insert(unit_idx, valor);
+        self.intents.clear();
+    }
+
+    #[func]
+    fn clear_unit_valor(&mut self, unit_idx: UnitIdx) {
+        self.unit_valor.remove(&unit_idx);
+        self.intents.clear();
+    }
+
+    /// Returns the `BehaviourKey`s currently active for `unit_idx` according
+    /// to the thresholds of its personality, in ascending order.
+    #[func]
+    fn get_active_keys(
+        &self,
+        unit_idx: UnitIdx,
+        unit_states: Gd<UnitStates>,
+        army_states: Gd<ArmyStates>,
+    ) -> Array<u8> {
+        self.with_battle(&unit_states.bind(), &army_states.bind(), |battle| {
+            UnitAssessment::for_unit(unit_idx, battle)
+                .map(|assessment| {
+                    assessment
+                        .active_keys
+                        .into_iter()
+                        .map(|key| key as u8)
+                        .collect()
+                })
+                .unwrap_or_default()
+        })
+    }
+
+    /// Returns the behaviour `unit_idx` follows in its current situation, the
+    /// active conditional behaviour with the highest priority or the default
+    /// one of its personality.
+    ///
+    /// The returned dictionary has the following structure:
+    ///```
+    /// {
+    ///     priority: <priority>,
+    ///     movement: <movement_behaviour>,
+    ///     action: <action_behaviour>,
+    /// }
+    ///```
+    /// The dictionary is empty if the unit cannot be assessed.
+    #[func]
+    fn get_active_behaviour(
+        &self,
+        unit_idx: UnitIdx,
+        unit_states: Gd<UnitStates>,
+        army_states: Gd<ArmyStates>,
+    ) -> Dictionary {
+        self.with_battle(&unit_states.bind(), &army_states.bind(), |battle| {
+            UnitAssessment::for_unit(unit_idx, battle)
+                .map(|assessment| assessment.behaviour.to_godot())
+                .unwrap_or_default()
+        })
+    }
+
+    /// Decides where `unit_idx` moves and what it does afterwards, using the
+    /// conditional behaviour of its personality that applies to its current
+    /// situation or the default one.
+    ///
+    /// The returned dictionary has the following structure:
+    ///```
+    /// {
+    ///     unit_idx: <unit_idx>,
+    ///     behaviour: { priority: <priority>, movement: <movement_behaviour>, action: <action_behaviour> },
+    ///     move_to: <cell>,
+    ///     action: <0: wait, 1: attack, 2: heal, 3: buff, 4: debuff>,
+    ///     target_idx: <target_unit_idx>,
+    ///     slot_idx: <inventory_slot_idx>,
+    /// }
+    ///```
+    /// `target_idx` and `slot_idx` are -1 when the unit waits. The dictionary
+    /// also contains a `trace` key when tracing is enabled, see `set_tracing`.
+    /// It is empty if the unit cannot be planned.
+    #[func]
+    fn plan_unit(
+        &self,
+        unit_idx: UnitIdx,
+        unit_states: Gd<UnitStates>,
+        army_states: Gd<ArmyStates>,
+    ) -> Dictionary {
+        self.with_battle(&unit_states.bind(), &army_states.bind(), |battle| {
+            PlanContext::for_unit(unit_idx, battle)
+                .map(|context| context.plan(self.tracing).to_godot())
+                .unwrap_or_default()
+        })
+    }
+
+    /// Plans the phase of the army currently active in `army_states`, deciding
+    /// the order in which its units act. Every unit is planned taking into
+    /// account the moves and expected outcome of the units acting before it:
+    /// attacks expected to defeat their target go first, then the rest of the
+    /// attacks, debuffs, buffs and heals, and finally the units that only
+    /// move, the ones closer to hostile units first.
+    ///
+    /// Returns the plans in acting order, with the same structure as the ones
+    /// returned by `plan_unit`.
+    #[func]
+    fn plan_army_phase(
+        &self,
+        unit_states: Gd<UnitStates>,
+        army_states: Gd<ArmyStates>,
+    ) -> Array<Dictionary> {
+        let states = unit_states.bind();
+        let armies = army_states.bind();
+
+        let army_id = armies.try_get_active_army_id();
+        let Some(army_units) = states.army_units.get(&army_id) else {
+            godot_error!("Active army [{}] has no units!", army_id);
+            return Array::new();
+        };
+
+        let unit_idxs = army_units.iter().copied().collect::<Vec<_>>();
+
+        self.with_battle(&states, &armies, |battle| {
+            plan_phase(&unit_idxs, battle, self.tracing)
+                .into_iter()
+                .map(|unit_plan| unit_plan.to_godot())
+                .collect()
+        })
+    }
+
+    /// Returns what the units of `army_id` plan to do in the next phase of
+    /// their army, planned as in `plan_army_phase`, so the UI can telegraph
+    /// them.
+    ///
+    /// The returned dictionary has the following structure:
+    ///```
+    /// {
+    ///     <unit_idx>: {
+    ///         order: <acting_order>,
+    ///         move_to: <cell>,
+    ///         action: <0: wait, 1: attack, 2: heal, 3: buff, 4: debuff>,
+    ///         target_idx: <target_unit_idx>,
+    ///         target_cell: <target_cell>,
+    ///     },
+    /// }
+    ///```
+    /// `target_idx` is -1 and `target_cell` is Vector2i(-1, -1) when the
+    /// unit only moves. `target_cell` takes into account the moves of the
+    /// units acting before.
+    ///
+    /// Intents are only planned again once units move, their hit points
+    /// change or the turn advances. Call `invalidate_intents` after changes
+    /// not covered by those, like inventory or stat changes.
+    #[func]
+    fn get_planned_intents(
+        &mut self,
+        army_id: ArmyId,
+        unit_states: Gd<UnitStates>,
+        army_states: Gd<ArmyStates>,
+    ) -> Dictionary {
+        let states = unit_states.bind();
+        let armies = army_states.bind();
+
+        let snapshot = BattleSnapshot::of(&states, armies.current_turn);
+
+        if let Some(cache) = self
+            .intents
+            .get(&army_id)
+            .filter(|cache| cache.snapshot == snapshot)
+        {
+            return cache.to_intents();
+        }
+
+        let Some(army_units) = states.army_units.get(&army_id) else {
+            godot_error!("Army [{}] has no units!", army_id);
+            return Dictionary::new();
+        };
+
+        let unit_idxs = army_units.iter().copied().collect::<Vec<_>>();
+
+        let plans = self.with_battle(&states, &armies, |battle| {
+            plan_phase(&unit_idxs, battle, false)
+        });
+
+        let cache = IntentCache { snapshot, plans };
+        let intents = cache.to_intents();
+
+        self.intents.insert(army_id, cache);
+
+        intents
+    }
+
+    #[func]
+    fn invalidate_intents(&mut self) {
+        self.intents.clear();
+    }
+}
+
+impl AiPlanner {
+    /// Calls `f` with a view of the battle state of `unit_states` and
+    /// `army_states` on the planner's map.
+    fn with_battle<R, F>(&self, unit_states: &UnitStates, army_states: &ArmyStates, f: F) -> R
+    where
+        F: FnOnce(&BattleView) -> R,
+    {
+        let db = self.db.bind();
+        let grid = self.terrain_grid.bind();
+        let unit_htp = HashMap::new();
+
+        f(&BattleView {
+            db: &db,
+            unit_states,
+            army_states,
+            terrain_grid: &grid,
+            zone_of_control: self.zone_of_control,
+            unit_valor: &self.unit_valor,
+            unit_htp: &unit_htp,
+            objectives: self.objectives.as_ref(),
+            difficulty: self.difficulty,
+            rules: self.rules,
+            phase_cache: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        database::{
+            chapter::DefendRadii,
+            combat_rules::{PiercingMitigation, TriangleBonus},
+            inventory::{WeaponColor, WeaponDamageType},
+            personality::{
+                ActionBehaviour, BehaviourKey, BehaviourThresholds, MovementBehaviour,
+                UnitBehaviour,
+            },
+        },
+        pathfinding::RustPathfinder,
+    };
+
+    use std::collections::HashSet;
+
+    const COSTS: &[i32] = &[1];
+
+    fn unit_at(unit_idx: UnitIdx, cell: Vector2i, hostile: bool) -> UnitView {
+        UnitView {
+            unit_idx,
+            cell,
+            hostile,
+            current_htp: 20,
+            max_htp: 20,
+            move_range: 3,
+            attack_range: Vector2i::new(1, 1),
+            attack_power: 10,
+            damage_type: Some(WeaponDamageType::Physical),
+            weapon_color: Some(WeaponColor::Red),
+            def: 2,
+            spt: 2,
+        }
+    }
+
+    fn context_for<'a>(
+        terrain_grid: &'a TerrainGrid,
+        movement: MovementBehaviour,
+        action: ActionBehaviour,
+        unit: UnitView,
+        units: Vec<UnitView>,
+    ) -> PlanContext<'a> {
+        let occupied = units.iter().map(|other| other.cell).collect::<HashSet<_>>();
+
+        let move_costs = RustPathfinder::movement_costs_with(
+            unit.cell,
+            unit.move_range,
+            terrain_grid.grid_bounds,
+            terrain_grid.topology,
+            |_| false,
+            |_, neighbour| (!occupied.contains(&neighbour)).then_some(1),
+        );
+
+        PlanContext {
+            unit,
+            units,
+            behaviour: UnitBehaviour {
+                priority: 0,
+                movement,
+                action,
+            },
+            active_keys: Vec::new(),
+            weapon_slot: Some(0),
+            support_options: Vec::new(),
+            move_costs,
+            costs: COSTS,
+            defend_cell: None,
+            defend_radii: DefendRadii::default(),
+            danger_zone: HashSet::new(),
+            objectives: UnitObjectives::default(),
+            difficulty: DifficultyProfile::default(),
+            rules: CombatRules::default(),
+            turn: 0,
+            terrain_grid,
+            phase_cache: None,
+        }
+    }
+
+    fn open_grid(size: Vector2i) -> TerrainGrid {
+        TerrainGrid::new(Rect2i::new(Vector2i::new(0, 0), size), 0)
+    }
+
+    mod active_keys {
+        use super::*;
+        use crate::{ai::behaviour::active_keys, pathfinding::GridTopology};
+
+        #[test]
+        fn active_keys_reports_low_htp_and_close_enemies() {
+            let mut unit = unit_at(0, Vector2i::new(0, 0), false);
+            unit.current_htp = 5;
+
+            let units = vec![
+                unit_at(1, Vector2i::new(2, 0), true),
+                unit_at(2, Vector2i::new(0, 3), true),
+                unit_at(3, Vector2i::new(1, 1), false),
+                // Too far away to be taken into account.
+                unit_at(4, Vector2i::new(8, 8), false),
+            ];
+
+            assert_eq!(
+                active_keys(
+                    &unit,
+                    &units,
+                    None,
+                    &BehaviourThresholds::default(),
+                    GridTopology::Square4
+                ),
+                vec![BehaviourKey::LowHtp, BehaviourKey::EnemiesClose]
+            );
+        }
+
+        #[test]
+        fn active_keys_uses_personality_thresholds() {
+            let mut unit = unit_at(0, Vector2i::new(0, 0), false);
+            unit.current_htp = 10;
+
+            let units = vec![
+                unit_at(1, Vector2i::new(2, 0), true),
+                unit_at(2, Vector2i::new(1, 0), false),
+                unit_at(3, Vector2i::new(0, 1), false),
+            ];
+
+            let thresholds = BehaviourThresholds {
+                low_htp_percent: 50,
+                low_valor: 20,
+                close_radius: 1,
+            };
+
+            assert_eq!(
+                active_keys(&unit, &units, Some(20), &thresholds, GridTopology::Square4),
+                vec![
+                    BehaviourKey::LowHtp,
+                    BehaviourKey::LowValor,
+                    BehaviourKey::AlliesClose
+                ]
+            );
+            assert_eq!(
+                active_keys(&unit, &units, Some(21), &thresholds, GridTopology::Square4),
+                vec![BehaviourKey::LowHtp, BehaviourKey::AlliesClose]
+            );
+        }
+    }
+
+    mod plan {
+        use super::*;
+
+        #[test]
+        fn plan_attacks_the_enemy_taking_most_damage() {
+            let grid = open_grid(Vector2i::new(6, 6));
+            let mut armored = unit_at(1, Vector2i::new(1, 0), true);
+            armored.def = 8;
+
+            let context = context_for(
+                &grid,
+                MovementBehaviour::Vanguard,
+                ActionBehaviour::AttackWeakerEnemy,
+                unit_at(0, Vector2i::new(0, 0), false),
+                vec![armored, unit_at(2, Vector2i::new(2, 2), true)],
+            );
+
+            let unit_plan = context.plan(false);
+
+            assert_eq!(unit_plan.action, AiAction::Attack);
+            assert_eq!(unit_plan.target_idx, Some(2));
+            assert_eq!(
+                grid.topology
+                    .distance(unit_plan.move_to, Vector2i::new(2, 2)),
+                1
+            );
+        }
+
+        #[test]
+        fn plan_heals_the_most_wounded_ally() {
+            let grid = open_grid(Vector2i::new(6, 6));
+            let mut wounded = unit_at(1, Vector2i::new(2, 0), false);
+            wounded.current_htp = 15;
+            let mut badly_wounded = unit_at(2, Vector2i::new(0, 2), false);
+            badly_wounded.current_htp = 5;
+
+            let mut context = context_for(
+                &grid,
+                MovementBehaviour::Vanguard,
+                ActionBehaviour::HealAllies,
+                unit_at(0, Vector2i::new(0, 0), false),
+                vec![wounded, badly_wounded],
+            );
+            context.support_options.push(SupportOption {
+                slot_idx: 2,
+                kind: SupportKind::Heal(10),
+                side: SupportSide::Friendly,
+                range: Vector2i::new(1, 1),
+                every_target: false,
+                area: None,
+                consumable: false,
+            });
+
+            let unit_plan = context.plan(false);
+
+            assert_eq!(unit_plan.action, AiAction::Heal);
+            assert_eq!(unit_plan.target_idx, Some(2));
+            assert_eq!(unit_plan.slot_idx, Some(2));
+        }
+
+        #[test]
+        fn plan_moves_according_to_movement_behaviour() {
+            let grid = open_grid(Vector2i::new(10, 1));
+            let unit = unit_at(0, Vector2i::new(4, 0), false);
+            let enemy = unit_at(1, Vector2i::new(9, 0), true);
+
+            let plan_with = |movement| {
+                context_for(
+                    &grid,
+                    movement,
+                    ActionBehaviour::DoNothing,
+                    unit.clone(),
+                    vec![enemy.clone()],
+                )
+                .plan(false)
+            };
+
+            let stationary_plan = plan_with(MovementBehaviour::Stationary);
+            assert_eq!(stationary_plan.action, AiAction::Wait);
+            assert_eq!(stationary_plan.move_to, Vector2i::new(4, 0));
+
+            assert_eq!(
+                plan_with(MovementBehaviour::Vanguard).move_to,
+                Vector2i::new(7, 0)
+            );
+            assert_eq!(
+                plan_with(MovementBehaviour::Evade).move_to,
+                Vector2i::new(1, 0)
+            );
+        }
+    }
+
+    mod defend {
+        use super::*;
+
+        fn defender_context<'a>(
+            terrain_grid: &'a TerrainGrid,
+            unit: UnitView,
+            enemy: UnitView,
+        ) -> PlanContext<'a> {
+            let mut context = context_for(
+                terrain_grid,
+                MovementBehaviour::Defend,
+                ActionBehaviour::AttackCloserEnemy,
+                unit,
+                vec![enemy],
+            );
+            context.defend_cell = Some(Vector2i::new(0, 0));
+            context.defend_radii = DefendRadii {
+                leash_radius: 2,
+                aggro_radius: Some(3),
+            };
+
+            context
+        }
+
+        #[test]
+        fn defend_engages_intruders_within_aggro_radius() {
+            let grid = open_grid(Vector2i::new(10, 10));
+
+            let context = defender_context(
+                &grid,
+                unit_at(0, Vector2i::new(0, 0), false),
+                unit_at(1, Vector2i::new(2, 1), true),
+            );
+
+            let unit_plan = context.plan(false);
+
+            assert_eq!(unit_plan.action, AiAction::Attack);
+            assert_eq!(unit_plan.target_idx, Some(1));
+            assert!(
+                grid.topology
+                    .distance(unit_plan.move_to, Vector2i::new(0, 0))
+                    <= 2
+            );
+        }
+
+        #[test]
+        fn defend_ignores_enemies_outside_aggro_radius() {
+            let grid = open_grid(Vector2i::new(10, 10));
+
+            // Reachable within the leash radius but too far from the defend cell.
+            let context = defender_context(
+                &grid,
+                unit_at(0, Vector2i::new(0, 0), false),
+                unit_at(1, Vector2i::new(3, 1), true),
+            );
+
+            let unit_plan = context.plan(false);
+
+            assert_eq!(unit_plan.action, AiAction::Wait);
+            assert_eq!(unit_plan.move_to, Vector2i::new(0, 0));
+        }
+
+        #[test]
+        fn defend_returns_to_its_post() {
+            let grid = open_grid(Vector2i::new(10, 10));
+
+            let context = defender_context(
+                &grid,
+                unit_at(0, Vector2i::new(6, 0), false),
+                unit_at(1, Vector2i::new(9, 9), true),
+            );
+
+            let unit_plan = context.plan(false);
+
+            assert_eq!(unit_plan.action, AiAction::Wait);
+            assert_eq!(unit_plan.move_to, Vector2i::new(3, 0));
+        }
+    }
+
+    mod objectives {
+        use super::*;
+
+        #[test]
+        fn tactician_occupies_contested_cells() {
+            let grid = open_grid(Vector2i::new(10, 10));
+
+            let mut context = context_for(
+                &grid,
+                MovementBehaviour::Tactician,
+                ActionBehaviour::DoNothing,
+                unit_at(0, Vector2i::new(0, 0), false),
+                vec![unit_at(1, Vector2i::new(0, 9), true)],
+            );
+            assert_eq!(context.plan(false).move_to, Vector2i::new(0, 3));
+
+            context
+                .objectives
+                .contested_cells
+                .insert(Vector2i::new(6, 0));
+            assert_eq!(context.plan(false).move_to, Vector2i::new(3, 0));
+
+            context.objectives.contested_cells = HashSet::from([Vector2i::new(1, 1)]);
+            context.danger_zone.insert(Vector2i::new(1, 1));
+            assert_eq!(context.plan(false).move_to, Vector2i::new(1, 1));
+        }
+
+        #[test]
+        fn tactician_attacks_critical_units_first() {
+            let grid = open_grid(Vector2i::new(6, 6));
+            let mut armored = unit_at(1, Vector2i::new(1, 0), true);
+            armored.def = 8;
+
+            let mut context = context_for(
+                &grid,
+                MovementBehaviour::Tactician,
+                ActionBehaviour::AttackWeakerEnemy,
+                unit_at(0, Vector2i::new(0, 0), false),
+                vec![armored, unit_at(2, Vector2i::new(0, 1), true)],
+            );
+            assert_eq!(context.plan(false).target_idx, Some(2));
+
+            context.objectives.critical_idxs.insert(1);
+            assert_eq!(context.plan(false).target_idx, Some(1));
+        }
+
+        #[test]
+        fn tactician_attacks_units_threatening_protected_allies() {
+            let grid = open_grid(Vector2i::new(10, 10));
+            let mut armored = unit_at(2, Vector2i::new(1, 2), true);
+            armored.def = 8;
+
+            let mut context = context_for(
+                &grid,
+                MovementBehaviour::Tactician,
+                ActionBehaviour::AttackWeakerEnemy,
+                unit_at(0, Vector2i::new(0, 0), false),
+                vec![
+                    unit_at(1, Vector2i::new(1, 5), false),
+                    armored,
+                    unit_at(3, Vector2i::new(2, 0), true),
+                ],
+            );
+            assert_eq!(context.plan(false).target_idx, Some(3));
+
+            context.objectives.protected_idxs.insert(1);
+            assert_eq!(context.plan(false).target_idx, Some(2));
+        }
+    }
+
+    mod difficulty {
+        use super::*;
+        use crate::ai::behaviour::difficulty_thresholds;
+
+        #[test]
+        fn difficulty_thresholds_scales_low_htp_percent() {
+            let thresholds = BehaviourThresholds {
+                low_htp_percent: 40,
+                low_valor: 10,
+                close_radius: 2,
+            };
+
+            let reckless = DifficultyProfile {
+                caution_percent: 50,
+                ..Default::default()
+            };
+            assert_eq!(
+                difficulty_thresholds(&thresholds, &reckless),
+                BehaviourThresholds {
+                    low_htp_percent: 20,
+                    ..thresholds
+                }
+            );
+
+            let fearful = DifficultyProfile {
+                caution_percent: 200,
+                ..Default::default()
+            };
+            assert_eq!(
+                difficulty_thresholds(&thresholds, &fearful).low_htp_percent,
+                80
+            );
+        }
+
+        #[test]
+        fn difficulty_prevents_suicidal_attacks() {
+            let grid = open_grid(Vector2i::new(6, 6));
+            let mut unit = unit_at(0, Vector2i::new(0, 0), false);
+            unit.current_htp = 5;
+
+            let mut context = context_for(
+                &grid,
+                MovementBehaviour::Stationary,
+                ActionBehaviour::AttackCloserEnemy,
+                unit,
+                vec![unit_at(1, Vector2i::new(1, 0), true)],
+            );
+            assert_eq!(context.plan(false).action, AiAction::Attack);
+
+            context.difficulty.allow_suicide = false;
+            assert_eq!(context.plan(false).action, AiAction::Wait);
+        }
+
+        #[test]
+        fn difficulty_jitter_is_bounded_and_reproducible() {
+            let grid = open_grid(Vector2i::new(10, 10));
+
+            let mut context = context_for(
+                &grid,
+                MovementBehaviour::Stationary,
+                ActionBehaviour::AttackCloserEnemy,
+                unit_at(0, Vector2i::new(4, 4), false),
+                vec![
+                    unit_at(1, Vector2i::new(4, 5), true),
+                    unit_at(2, Vector2i::new(5, 4), true),
+                    unit_at(3, Vector2i::new(3, 4), true),
+                ],
+            );
+
+            let scores_of = |context: &PlanContext| {
+                context
+                    .plan(true)
+                    .trace
+                    .expect("Plan should include a trace")
+                    .candidates
+                    .into_iter()
+                    .map(|candidate| candidate.score[1])
+                    .collect::<Vec<_>>()
+            };
+
+            let base_scores = scores_of(&context);
+
+            context.difficulty.target_jitter = 3;
+            let jitter_scores = scores_of(&context);
+
+            assert_eq!(jitter_scores, scores_of(&context));
+            assert!(
+                base_scores
+                    .iter()
+                    .zip(jitter_scores.iter())
+                    .all(|(base, jitter)| (0..=3).contains(&(jitter - base)))
+            );
+        }
+    }
+
+    mod intents {
+        use super::*;
+
+        fn plan_for(unit_idx: UnitIdx, move_to: Vector2i, target_idx: Option<UnitIdx>) -> UnitPlan {
+            UnitPlan {
+                unit_idx,
+                behaviour: UnitBehaviour {
+                    priority: 0,
+                    movement: MovementBehaviour::Vanguard,
+                    action: ActionBehaviour::AttackCloserEnemy,
+                },
+                move_to,
+                action: if target_idx.is_some() {
+                    AiAction::Attack
+                } else {
+                    AiAction::Wait
+                },
+                target_idx,
+                slot_idx: target_idx.map(|_| 0),
+                trace: None,
+            }
+        }
+
+        #[test]
+        fn snapshot_changes_when_units_move() {
+            let mut unit_states = UnitStates::default();
+            unit_states.unit_idx_to_cell.insert(0, Vector2i::new(0, 0));
+            unit_states.unit_idx_to_cell.insert(1, Vector2i::new(4, 0));
+
+            let snapshot = BattleSnapshot::of(&unit_states, 1);
+            assert_eq!(snapshot, BattleSnapshot::of(&unit_states, 1));
+            assert_ne!(snapshot, BattleSnapshot::of(&unit_states, 2));
+
+            unit_states.unit_idx_to_cell.insert(1, Vector2i::new(5, 0));
+            assert_ne!(snapshot, BattleSnapshot::of(&unit_states, 1));
+        }
+
+        #[test]
+        fn target_cells_follow_earlier_moves() {
+            let mut unit_states = UnitStates::default();
+            unit_states.unit_idx_to_cell.insert(0, Vector2i::new(0, 0));
+            unit_states.unit_idx_to_cell.insert(1, Vector2i::new(2, 0));
+            unit_states.unit_idx_to_cell.insert(2, Vector2i::new(6, 0));
+
+            let cache = IntentCache {
+                snapshot: BattleSnapshot::of(&unit_states, 1),
+                plans: vec![
+                    plan_for(1, Vector2i::new(3, 0), None),
+                    plan_for(0, Vector2i::new(4, 0), Some(1)),
+                    plan_for(2, Vector2i::new(5, 0), Some(0)),
+                ],
+            };
+
+            assert_eq!(
+                cache.target_cells(),
+                vec![None, Some(Vector2i::new(3, 0)), Some(Vector2i::new(4, 0))]
+            );
+        }
+    }
+
+    mod plan_tracing {
+        use super::*;
+
+        #[test]
+        fn plan_traces_the_evaluated_candidates() {
+            let grid = open_grid(Vector2i::new(6, 6));
+            let mut armored = unit_at(1, Vector2i::new(1, 0), true);
+            armored.def = 8;
+
+            let mut context = context_for(
+                &grid,
+                MovementBehaviour::Vanguard,
+                ActionBehaviour::AttackWeakerEnemy,
+                unit_at(0, Vector2i::new(0, 0), false),
+                vec![armored, unit_at(2, Vector2i::new(2, 2), true)],
+            );
+            context.active_keys = vec![BehaviourKey::EnemiesClose];
+
+            assert!(context.plan(false).trace.is_none());
+
+            let unit_plan = context.plan(true);
+            let trace = unit_plan.trace.expect("Plan should include a trace");
+
+            assert_eq!(trace.active_keys, vec![BehaviourKey::EnemiesClose]);
+            assert_eq!(trace.behaviour, Some(context.behaviour));
+            assert_eq!(trace.cells, context.candidate_cells());
+            assert!(trace.movement.is_empty());
+
+            let scores = trace
+                .candidates
+                .iter()
+                .map(|candidate| (candidate.target_idx, candidate.score.clone()))
+                .collect::<Vec<_>>();
+            assert_eq!(scores, vec![(1, vec![2, -2, 20]), (2, vec![2, -8, 20])]);
+        }
+
+        #[test]
+        fn plan_traces_movement_scores_when_waiting() {
+            let grid = open_grid(Vector2i::new(10, 1));
+
+            let context = context_for(
+                &grid,
+                MovementBehaviour::Vanguard,
+                ActionBehaviour::DoNothing,
+                unit_at(0, Vector2i::new(4, 0), false),
+                vec![unit_at(1, Vector2i::new(9, 0), true)],
+            );
+
+            let unit_plan = context.plan(true);
+            let trace = unit_plan.trace.expect("Plan should include a trace");
+
+            assert!(trace.candidates.is_empty());
+            assert_eq!(trace.movement.len(), trace.cells.len());
+
+            let (best_cell, _) = trace
+                .movement
+                .iter()
+                .min_by_key(|(_, score)| score.clone())
+                .expect("Trace should include movement scores");
+            assert_eq!(*best_cell, unit_plan.move_to);
+        }
+    }
+
+    mod rules {
+        use super::*;
+
+        #[test]
+        fn estimated_damage_follows_the_combat_rules() {
+            let grid = open_grid(Vector2i::new(6, 6));
+            let attacker = unit_at(0, Vector2i::new(0, 0), false);
+            let mut target = unit_at(1, Vector2i::new(1, 0), true);
+
+            let rules = CombatRules {
+                triangle: TriangleBonus { hit: 10, damage: 2 },
+                ..Default::default()
+            };
+
+            // 10 attack_power - 2 def
+            assert_eq!(
+                attacker.estimated_damage(&target, &grid, &CombatRules::default()),
+                8
+            );
+
+            target.weapon_color = Some(WeaponColor::Green);
+            assert_eq!(attacker.estimated_damage(&target, &grid, &rules), 10);
+            target.weapon_color = Some(WeaponColor::Blue);
+            assert_eq!(attacker.estimated_damage(&target, &grid, &rules), 6);
+            target.weapon_color = None;
+            assert_eq!(attacker.estimated_damage(&target, &grid, &rules), 8);
+
+            let mut piercing = attacker.clone();
+            piercing.damage_type = Some(WeaponDamageType::Piercing);
+            target.def = 6;
+
+            let damage_with = |mitigation| {
+                let rules = CombatRules {
+                    piercing: mitigation,
+                    ..Default::default()
+                };
+
+                piercing.estimated_damage(&target, &grid, &rules)
+            };
+
+            assert_eq!(damage_with(PiercingMitigation::Lowest), 8);
+            assert_eq!(damage_with(PiercingMitigation::Halved), 9);
+            assert_eq!(damage_with(PiercingMitigation::Ignored), 10);
+        }
+
+        #[test]
+        fn triangle_advantage_avoids_suicidal_attacks() {
+            let grid = open_grid(Vector2i::new(6, 6));
+            let mut unit = unit_at(0, Vector2i::new(0, 0), false);
+            unit.current_htp = 8;
+            let mut enemy = unit_at(1, Vector2i::new(1, 0), true);
+            enemy.weapon_color = Some(WeaponColor::Green);
+
+            let mut context = context_for(
+                &grid,
+                MovementBehaviour::Stationary,
+                ActionBehaviour::AttackCloserEnemy,
+                unit,
+                vec![enemy],
+            );
+            context.difficulty.allow_suicide = false;
+
+            // The counter attack of 8 damage would defeat the unit.
+            assert_eq!(context.plan(false).action, AiAction::Wait);
+
+            context.rules.triangle = TriangleBonus { hit: 0, damage: 2 };
+            assert_eq!(context.plan(false).action, AiAction::Attack);
+        }
+    }
+
+    mod phase_rank {
+        use super::*;
+
+        #[test]
+        fn phase_rank_puts_defeating_attacks_first() {
+            let grid = open_grid(Vector2i::new(6, 6));
+            let mut weakened = unit_at(1, Vector2i::new(1, 0), true);
+            weakened.current_htp = 6;
+
+            let context = context_for(
+                &grid,
+                MovementBehaviour::Stationary,
+                ActionBehaviour::AttackWeakerEnemy,
+                unit_at(0, Vector2i::new(0, 0), false),
+                vec![weakened, unit_at(2, Vector2i::new(4, 4), true)],
+            );
+
+            let attack_plan = context.plan(false);
+            assert_eq!(attack_plan.target_idx, Some(1));
+            assert_eq!(context.phase_rank(&attack_plan), (0, 0));
+
+            let mut strong = unit_at(1, Vector2i::new(1, 0), true);
+            strong.current_htp = 20;
+            let context = context_for(
+                &grid,
+                MovementBehaviour::Stationary,
+                ActionBehaviour::AttackWeakerEnemy,
+                unit_at(0, Vector2i::new(0, 0), false),
+                vec![strong],
+            );
+
+            let attack_plan = context.plan(false);
+            assert_eq!(context.phase_rank(&attack_plan), (1, -8));
+
+            let wait_plan = UnitPlan {
+                action: AiAction::Wait,
+                target_idx: None,
+                slot_idx: None,
+                ..attack_plan.clone()
+            };
+            assert!(context.phase_rank(&attack_plan) < context.phase_rank(&wait_plan));
+        }
+    }
+
+    mod phase_cache {
+        use super::*;
+        use std::rc::Rc;
+
+        #[test]
+        fn phase_cache_shares_flow_fields_by_goals() {
+            let grid = open_grid(Vector2i::new(6, 6));
+            let phase_cache = PhaseCache::default();
+            let mut computed = 0;
+
+            let mut flow_field = |goals: Vec<Vector2i>| {
+                phase_cache.flow_field(COSTS, goals, |goals| {
+                    computed += 1;
+                    RustPathfinder::flow_field_with(
+                        goals.iter().copied(),
+                        grid.grid_bounds,
+                        grid.topology,
+                        |_, _| Some(1),
+                    )
+                })
+            };
+
+            let first = flow_field(vec![Vector2i::new(4, 4), Vector2i::new(1, 5)]);
+            let reordered = flow_field(vec![Vector2i::new(1, 5), Vector2i::new(4, 4)]);
+            let other = flow_field(vec![Vector2i::new(4, 4)]);
+
+            assert!(Rc::ptr_eq(&first, &reordered));
+            assert!(!Rc::ptr_eq(&first, &other));
+            assert_eq!(computed, 2);
+        }
+
+        #[test]
+        fn phase_cache_keeps_fallback_moves() {
+            let grid = open_grid(Vector2i::new(8, 8));
+            let phase_cache = PhaseCache::default();
+
+            let plans = [None, Some(&phase_cache), Some(&phase_cache)].map(|phase_cache| {
+                let mut context = context_for(
+                    &grid,
+                    MovementBehaviour::Vanguard,
+                    ActionBehaviour::AttackWeakerEnemy,
+                    unit_at(0, Vector2i::new(0, 0), false),
+                    vec![unit_at(1, Vector2i::new(7, 6), true)],
+                );
+                context.phase_cache = phase_cache;
+
+                context.plan(false).move_to
+            });
+
+            assert_eq!(plans[0], plans[1]);
+            assert_eq!(plans[1], plans[2]);
+        }
+    }
+}
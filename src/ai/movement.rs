@@ -0,0 +1,179 @@
+use super::{PlanContext, PlanTrace};
+use crate::{database::personality::MovementBehaviour, pathfinding::RustPathfinder};
+
+use godot::prelude::*;
+use std::{cmp::Reverse, rc::Rc};
+
+impl PlanContext<'_> {
+    /// Returns the cells the unit can end its movement on according to its
+    /// `MovementBehaviour`, together with their cost, the preferred ones first.
+    ///
+    /// `Stationary` units can only stay in place and `Defend` units cannot
+    /// move further away from their defend cell than their leash radius, or
+    /// than they already are if they are outside of it.
+    pub(crate) fn candidate_cells(&self) -> Vec<(Vector2i, i32)> {
+        let origin = self.unit.cell;
+        let topology = self.terrain_grid.topology;
+
+        let mut cells = self
+            .move_costs
+            .iter()
+            .filter(|(cell, _)| match self.behaviour.movement {
+                MovementBehaviour::Stationary => **cell == origin,
+                MovementBehaviour::Defend => self.defend_cell.map_or(**cell == origin, |defend| {
+                    let leash = (self.defend_radii.leash_radius as i32)
+                        .max(topology.distance(origin, defend));
+
+                    topology.distance(**cell, defend) <= leash
+                }),
+                _ => true,
+            })
+            .map(|(cell, path_cost)| (*cell, *path_cost))
+            .collect::<Vec<_>>();
+
+        cells.sort_unstable_by_key(|(cell, path_cost)| self.cell_rank(*cell, *path_cost));
+
+        cells
+    }
+
+    /// Ranks `cell` for the unit's `MovementBehaviour`, lower ranks are
+    /// better. Ties are broken by terrain defense and avoid bonus, path cost
+    /// and cell position.
+    fn cell_rank(
+        &self,
+        cell: Vector2i,
+        path_cost: i32,
+    ) -> (i32, Reverse<i32>, Reverse<i32>, i32, i32, i32) {
+        let topology = self.terrain_grid.topology;
+
+        let preference = match self.behaviour.movement {
+            MovementBehaviour::Defend => self
+                .defend_cell
+                .map_or(0, |defend| topology.distance(cell, defend)),
+            MovementBehaviour::Evade => -self
+                .units
+                .iter()
+                .filter(|other| other.hostile)
+                .map(|other| topology.distance(cell, other.cell))
+                .min()
+                .unwrap_or(0),
+            MovementBehaviour::Tactician => {
+                if self.objectives.contested_cells.contains(&cell) {
+                    -1
+                } else {
+                    self.danger_zone.contains(&cell) as i32
+                }
+            }
+            MovementBehaviour::Stationary | MovementBehaviour::Vanguard => 0,
+        };
+
+        let bonus = self.terrain_grid.bonus_at(cell);
+
+        (
+            preference,
+            Reverse(bonus.def),
+            Reverse(bonus.avo),
+            path_cost,
+            cell.y,
+            cell.x,
+        )
+    }
+
+    /// Picks among `cells` the one to move to when the unit has no action to
+    /// perform, following the unit's `MovementBehaviour`:
+    /// * `Stationary` stays in place.
+    /// * `Defend` moves towards its defend cell.
+    /// * `Vanguard` moves towards the closest hostile unit.
+    /// * `Evade` moves as far as possible from the hostile units.
+    /// * `Tactician` moves towards the cells contested by hostile units and
+    ///   the units they have to defeat, or the closest hostile unit if there
+    ///   are none, avoiding the cells hostile units can attack next turn
+    ///   unless they are contested.
+    ///
+    /// The score of every cell considered is recorded in `trace`.
+    pub(crate) fn fallback_cell(
+        &self,
+        cells: &[(Vector2i, i32)],
+        trace: Option<&mut PlanTrace>,
+    ) -> Vector2i {
+        let origin = self.unit.cell;
+        let movement = self.behaviour.movement;
+
+        let objective_cells = match movement {
+            MovementBehaviour::Tactician => self.objective_cells(),
+            _ => Vec::new(),
+        };
+
+        let goals = match (movement, self.defend_cell) {
+            (MovementBehaviour::Stationary, _) | (MovementBehaviour::Defend, None) => {
+                return origin;
+            }
+            (MovementBehaviour::Defend, Some(defend)) => vec![defend],
+            (MovementBehaviour::Tactician, _) if !objective_cells.is_empty() => objective_cells,
+            _ => self
+                .units
+                .iter()
+                .filter(|other| other.hostile)
+                .map(|other| other.cell)
+                .collect(),
+        };
+
+        let flow_field_to = |goals: &[Vector2i]| {
+            RustPathfinder::flow_field_with(
+                goals.iter().copied(),
+                self.terrain_grid.grid_bounds,
+                self.terrain_grid.topology,
+                |cell, neighbour| self.terrain_grid.move_cost(self.costs, cell, neighbour),
+            )
+        };
+
+        let flow_field = match self.phase_cache {
+            Some(phase_cache) => phase_cache.flow_field(self.costs, goals, flow_field_to),
+            None => Rc::new(flow_field_to(&goals)),
+        };
+
+        let cell_score = |cell: Vector2i, path_cost: i32| {
+            let distance = flow_field.distance_at(cell).unwrap_or(i32::MAX);
+
+            let (in_danger, distance_rank) = match movement {
+                MovementBehaviour::Evade => (false, -distance),
+                MovementBehaviour::Tactician => (
+                    self.danger_zone.contains(&cell)
+                        && !self.objectives.contested_cells.contains(&cell),
+                    distance,
+                ),
+                _ => (false, distance),
+            };
+
+            [in_danger as i32, distance_rank, path_cost, cell.y, cell.x]
+        };
+
+        if let Some(trace) = trace {
+            trace.movement = cells
+                .iter()
+                .map(|(cell, path_cost)| (*cell, cell_score(*cell, *path_cost).to_vec()))
+                .collect();
+        }
+
+        cells
+            .iter()
+            .min_by_key(|(cell, path_cost)| cell_score(*cell, *path_cost))
+            .map_or(origin, |(cell, _)| *cell)
+    }
+
+    /// Cells a `Tactician` unit moves towards to follow its objectives, the
+    /// contested cells and the cells of the units it protects.
+    fn objective_cells(&self) -> Vec<Vector2i> {
+        self.objectives
+            .contested_cells
+            .iter()
+            .copied()
+            .chain(
+                self.units
+                    .iter()
+                    .filter(|other| self.objectives.protected_idxs.contains(&other.unit_idx))
+                    .map(|other| other.cell),
+            )
+            .collect()
+    }
+}
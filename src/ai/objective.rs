@@ -0,0 +1,118 @@
+use super::BattleView;
+use crate::{
+    database::{
+        army::ArmyId,
+        chapter::{DefeatCondition, VictoryCondition},
+        unit::UnitId,
+    },
+    game_entities::unit_data::UnitIdx,
+};
+
+use godot::prelude::*;
+use std::collections::{HashMap, HashSet};
+
+/// Objectives of a battle from the point of view of the armies hostile to
+/// the player army, taken from its `VictoryCondition` and `DefeatCondition`.
+#[derive(Clone, Default, Debug)]
+pub(crate) struct BattleObjectives {
+    /// Cells the player army has to reach or defend
+    contested_cells: HashSet<Vector2i>,
+    /// Units the player army has to defeat, per army
+    protected_units: HashMap<ArmyId, HashSet<UnitId>>,
+    /// Units of the player army whose defeat makes it lose the battle
+    critical_units: HashSet<UnitId>,
+}
+
+impl BattleObjectives {
+    pub(crate) fn from_conditions(victory: &VictoryCondition, defeat: &DefeatCondition) -> Self {
+        let mut objectives = Self::default();
+
+        match victory {
+            VictoryCondition::DefeatUnits(defeat_map) => {
+                objectives.protected_units = defeat_map
+                    .iter()
+                    .map(|(army_id, unit_ids)| {
+                        (army_id.clone(), unit_ids.iter().cloned().collect())
+                    })
+                    .collect();
+            }
+            VictoryCondition::DefendUntil { defend_cells, .. }
+            | VictoryCondition::DefendRout { defend_cells, .. } => {
+                objectives.contested_cells =
+                    defend_cells.iter().map(|cell| cell.to_godot()).collect();
+            }
+            VictoryCondition::ReachWithUnits { reach_cells, .. } => {
+                objectives.contested_cells =
+                    reach_cells.iter().map(|cell| cell.to_godot()).collect();
+            }
+            VictoryCondition::RoutArmies(_) => {}
+        }
+
+        if let DefeatCondition::UnitsDefeated(unit_ids) = defeat {
+            objectives.critical_units = unit_ids.iter().cloned().collect();
+        }
+
+        objectives
+    }
+
+    /// Resolves the objectives that apply to `unit_idx` in the `battle`
+    /// state. Units that are not hostile to the player army have none.
+    pub(crate) fn for_unit(&self, unit_idx: UnitIdx, battle: &BattleView) -> UnitObjectives {
+        let BattleView {
+            unit_states,
+            army_states,
+            ..
+        } = *battle;
+
+        let Some(army_id) = unit_states.unit_idx_to_army_id.get(&unit_idx) else {
+            return UnitObjectives::default();
+        };
+
+        if !army_states.are_hostile(army_id, &army_states.player_army) {
+            return UnitObjectives::default();
+        }
+
+        let mut objectives = UnitObjectives {
+            contested_cells: self.contested_cells.clone(),
+            ..Default::default()
+        };
+
+        for other_idx in unit_states.unit_idx_to_cell.keys() {
+            let (Some(other_army_id), Some(other_data)) = (
+                unit_states.unit_idx_to_army_id.get(other_idx),
+                unit_states.data_store.get(other_idx),
+            ) else {
+                continue;
+            };
+
+            let unit_id = &other_data.bind().unit_id;
+
+            if other_army_id == &army_states.player_army {
+                if self.critical_units.contains(unit_id) {
+                    objectives.critical_idxs.insert(*other_idx);
+                }
+            } else if other_idx != &unit_idx
+                && !army_states.are_hostile(army_id, other_army_id)
+                && self
+                    .protected_units
+                    .get(other_army_id)
+                    .is_some_and(|unit_ids| unit_ids.contains(unit_id))
+            {
+                objectives.protected_idxs.insert(*other_idx);
+            }
+        }
+
+        objectives
+    }
+}
+
+/// Objectives of the battle that apply to a single unit.
+#[derive(Clone, Default, Debug)]
+pub(crate) struct UnitObjectives {
+    /// Cells hostile units have to reach or defend, occupied to deny them
+    pub(crate) contested_cells: HashSet<Vector2i>,
+    /// Friendly units hostile units have to defeat
+    pub(crate) protected_idxs: HashSet<UnitIdx>,
+    /// Hostile units whose defeat makes their army lose the battle
+    pub(crate) critical_idxs: HashSet<UnitIdx>,
+}
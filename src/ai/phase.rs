@@ -0,0 +1,238 @@
+use super::{AiAction, BattleView, PlanContext, SupportKind, UnitPlan};
+use crate::{database::army::ArmyId, game_entities::unit_data::UnitIdx, pathfinding::FlowField};
+
+use godot::prelude::*;
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    rc::Rc,
+};
+
+/// Movement cost table and sorted goal cells of a flow field.
+type FlowFieldKey = (Vec<i32>, Vec<Vector2i>);
+
+/// Flow fields and danger zones shared by the units planned within a phase,
+/// so they are not computed again for every unit each time it is planned.
+#[derive(Default)]
+pub(crate) struct PhaseCache {
+    /// Flow fields by movement cost table and sorted goal cells, they do not
+    /// depend on the position of the units so they last the whole phase
+    flow_fields: RefCell<HashMap<FlowFieldKey, Rc<FlowField>>>,
+    /// Cells the hostile units of each army can attack next turn, they only
+    /// last until a planned unit moves or defeats another
+    danger_zones: RefCell<HashMap<ArmyId, HashSet<Vector2i>>>,
+}
+
+impl PhaseCache {
+    /// Returns the flow field towards `goals` for the `costs` table, calling
+    /// `flow_field_to` with the sorted goals if it was not computed yet.
+    pub(crate) fn flow_field<F>(
+        &self,
+        costs: &[i32],
+        mut goals: Vec<Vector2i>,
+        flow_field_to: F,
+    ) -> Rc<FlowField>
+    where
+        F: FnOnce(&[Vector2i]) -> FlowField,
+    {
+        goals.sort_unstable_by_key(|cell| (cell.y, cell.x));
+        goals.dedup();
+
+        self.flow_fields
+            .borrow_mut()
+            .entry((costs.to_vec(), goals))
+            .or_insert_with_key(|(_, goals)| Rc::new(flow_field_to(goals)))
+            .clone()
+    }
+
+    /// Returns the danger zone of the hostile armies of `army_id`, calling
+    /// `danger_zone` if it was not computed yet.
+    pub(crate) fn danger_zone<F>(&self, army_id: &ArmyId, danger_zone: F) -> HashSet<Vector2i>
+    where
+        F: FnOnce() -> HashSet<Vector2i>,
+    {
+        self.danger_zones
+            .borrow_mut()
+            .entry(army_id.clone())
+            .or_insert_with(danger_zone)
+            .clone()
+    }
+
+    /// Drops the danger zones, to be called whenever a unit moves or is
+    /// defeated.
+    pub(crate) fn clear_danger_zones(&mut self) {
+        self.danger_zones.get_mut().clear();
+    }
+}
+
+/// Outcome of a planned action on the hit points of its target.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) struct HtpChange {
+    pub(crate) target_idx: UnitIdx,
+    pub(crate) target_htp: i32,
+}
+
+impl PlanContext<'_> {
+    /// Estimates the hit points of the target of `unit_plan` once the action
+    /// is performed. Returns `None` for actions that do not change them.
+    pub(crate) fn htp_change(&self, unit_plan: &UnitPlan) -> Option<HtpChange> {
+        let target_idx = unit_plan.target_idx?;
+        let target = self
+            .units
+            .iter()
+            .find(|other| other.unit_idx == target_idx)
+            .unwrap_or(&self.unit);
+
+        let target_htp = match unit_plan.action {
+            AiAction::Attack => {
+                target.current_htp
+                    - self
+                        .unit
+                        .estimated_damage(target, self.terrain_grid, &self.rules)
+            }
+            AiAction::Heal => {
+                let Some(SupportKind::Heal(power)) = self
+                    .support_options
+                    .iter()
+                    .find(|option| Some(option.slot_idx) == unit_plan.slot_idx)
+                    .map(|option| option.kind)
+                else {
+                    return None;
+                };
+
+                (target.current_htp + power).min(target.max_htp)
+            }
+            AiAction::Wait | AiAction::Buff | AiAction::Debuff => return None,
+        };
+
+        Some(HtpChange {
+            target_idx,
+            target_htp: target_htp.max(0),
+        })
+    }
+
+    /// Ranks `unit_plan` to decide when the unit acts within the phase,
+    /// lower ranks act first:
+    /// 1. Attacks expected to defeat their target, so follow-up attacks can
+    ///    focus on other targets.
+    /// 2. Other attacks, the ones dealing more damage first.
+    /// 3. Debuffs, buffs and heals, in this order, so supports act once the
+    ///    outcome of the attacks is known.
+    /// 4. Units that only move, the ones ending closer to hostile units first
+    ///    so they do not block the path of those behind them.
+    pub(crate) fn phase_rank(&self, unit_plan: &UnitPlan) -> (u8, i32) {
+        match unit_plan.action {
+            AiAction::Attack => match self.htp_change(unit_plan) {
+                Some(change) if change.target_htp == 0 => (0, 0),
+                Some(change) => {
+                    let target_htp = self
+                        .units
+                        .iter()
+                        .find(|other| other.unit_idx == change.target_idx)
+                        .map_or(0, |target| target.current_htp);
+
+                    (1, change.target_htp - target_htp)
+                }
+                None => (1, 0),
+            },
+            AiAction::Debuff => (2, 0),
+            AiAction::Buff => (3, 0),
+            AiAction::Heal => (4, 0),
+            AiAction::Wait => {
+                let topology = self.terrain_grid.topology;
+
+                let front_distance = self
+                    .units
+                    .iter()
+                    .filter(|other| other.hostile)
+                    .map(|other| topology.distance(unit_plan.move_to, other.cell))
+                    .min()
+                    .unwrap_or(i32::MAX);
+
+                (5, front_distance)
+            }
+        }
+    }
+}
+
+/// Plans the phase of the units in `unit_idxs`, deciding the order in which
+/// they act. Units are planned one at a time on top of the outcome of the
+/// plans decided before them, picking every time the remaining unit whose
+/// plan ranks first in `PlanContext::phase_rank`. Flow fields and danger
+/// zones are shared through a `PhaseCache` while planning.
+///
+/// Units that cannot be planned are left out of the returned plans, which
+/// include their trace with their phase rank if `tracing` is **true**.
+pub(crate) fn plan_phase(
+    unit_idxs: &[UnitIdx],
+    battle: &BattleView,
+    tracing: bool,
+) -> Vec<UnitPlan> {
+    let mut unit_states = battle.unit_states.clone_without_ranges();
+    let mut phase_cache = PhaseCache::default();
+    let mut unit_htp = battle.unit_htp.clone();
+    let mut pending = unit_idxs.to_vec();
+    let mut phase_plans = Vec::with_capacity(pending.len());
+
+    while !pending.is_empty() {
+        let simulated = BattleView {
+            unit_states: &unit_states,
+            unit_htp: &unit_htp,
+            phase_cache: Some(&phase_cache),
+            ..*battle
+        };
+
+        let mut planned_units = Vec::with_capacity(pending.len());
+
+        pending.retain(|unit_idx| {
+            if let Some(context) = PlanContext::for_unit(*unit_idx, &simulated) {
+                let mut unit_plan = context.plan(tracing);
+                let rank = context.phase_rank(&unit_plan);
+
+                if let Some(trace) = unit_plan.trace.as_mut() {
+                    trace.phase_rank = Some(rank);
+                }
+                let htp_change = context.htp_change(&unit_plan);
+
+                planned_units.push((rank, unit_plan, htp_change));
+                true
+            } else {
+                false
+            }
+        });
+
+        let Some((_, unit_plan, htp_change)) = planned_units
+            .into_iter()
+            .min_by_key(|(rank, unit_plan, _)| (*rank, unit_plan.unit_idx))
+        else {
+            break;
+        };
+
+        pending.retain(|unit_idx| *unit_idx != unit_plan.unit_idx);
+        phase_cache.clear_danger_zones();
+
+        if let Some(old_cell) = unit_states
+            .unit_idx_to_cell
+            .insert(unit_plan.unit_idx, unit_plan.move_to)
+        {
+            unit_states.grid_cell_to_idx.remove(&old_cell);
+        }
+        unit_states
+            .grid_cell_to_idx
+            .insert(unit_plan.move_to, unit_plan.unit_idx);
+
+        if let Some(change) = htp_change {
+            unit_htp.insert(change.target_idx, change.target_htp);
+
+            if change.target_htp == 0 {
+                if let Some(defeated_at) = unit_states.unit_idx_to_cell.remove(&change.target_idx) {
+                    unit_states.grid_cell_to_idx.remove(&defeated_at);
+                }
+            }
+        }
+
+        phase_plans.push(unit_plan);
+    }
+
+    phase_plans
+}
@@ -0,0 +1,94 @@
+use super::{PlanContext, PlanTrace};
+use crate::{database::personality::UnitBehaviour, game_entities::unit_data::UnitIdx};
+
+use godot::prelude::*;
+
+/// Action performed by a unit after moving.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum AiAction {
+    Wait = 0,
+    Attack = 1,
+    Heal = 2,
+    Buff = 3,
+    Debuff = 4,
+}
+
+/// Decision taken by the planner for a single unit.
+#[derive(Clone, Debug)]
+pub(crate) struct UnitPlan {
+    pub(crate) unit_idx: UnitIdx,
+    pub(crate) behaviour: UnitBehaviour,
+    pub(crate) move_to: Vector2i,
+    pub(crate) action: AiAction,
+    pub(crate) target_idx: Option<UnitIdx>,
+    /// Inventory slot used to perform the action
+    pub(crate) slot_idx: Option<usize>,
+    pub(crate) trace: Option<PlanTrace>,
+}
+
+impl GodotConvert for UnitPlan {
+    type Via = Dictionary;
+}
+
+impl ToGodot for UnitPlan {
+    type ToVia<'v> = Dictionary;
+
+    fn to_godot(&self) -> Self::Via {
+        let mut plan_dict = dict! {
+            "unit_idx": self.unit_idx,
+            "behaviour": self.behaviour.to_godot(),
+            "move_to": self.move_to,
+            "action": self.action as u8,
+            "target_idx": self.target_idx.map_or(-1, i64::from),
+            "slot_idx": self.slot_idx.map_or(-1, |slot_idx| slot_idx as i64),
+        };
+
+        if let Some(trace) = &self.trace {
+            plan_dict.set("trace", trace.to_godot());
+        }
+
+        plan_dict
+    }
+}
+
+impl PlanContext<'_> {
+    /// Decides the cell the unit moves to and the action it performs there,
+    /// waiting at the cell picked by its movement behaviour if it has no
+    /// action to perform.
+    ///
+    /// A `PlanTrace` of the decision is attached to the returned plan if
+    /// `tracing` is **true**.
+    pub(crate) fn plan(&self, tracing: bool) -> UnitPlan {
+        let cells = self.candidate_cells();
+
+        let mut trace = tracing.then(|| PlanTrace {
+            active_keys: self.active_keys.clone(),
+            behaviour: Some(self.behaviour),
+            cells: cells.clone(),
+            ..Default::default()
+        });
+
+        let mut unit_plan = UnitPlan {
+            unit_idx: self.unit.unit_idx,
+            behaviour: self.behaviour,
+            move_to: self.unit.cell,
+            action: AiAction::Wait,
+            target_idx: None,
+            slot_idx: None,
+            trace: None,
+        };
+
+        if let Some(choice) = self.choose_action(&cells, trace.as_mut()) {
+            unit_plan.move_to = choice.cell;
+            unit_plan.action = choice.action;
+            unit_plan.target_idx = Some(choice.target_idx);
+            unit_plan.slot_idx = Some(choice.slot_idx);
+        } else {
+            unit_plan.move_to = self.fallback_cell(&cells, trace.as_mut());
+        }
+
+        unit_plan.trace = trace;
+
+        unit_plan
+    }
+}
@@ -0,0 +1,90 @@
+use super::AiAction;
+use crate::{
+    database::personality::{BehaviourKey, UnitBehaviour},
+    game_entities::unit_data::UnitIdx,
+};
+
+use godot::prelude::*;
+
+/// Action considered by the planner, `score` is compared in order and the
+/// lowest one is picked.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct TracedCandidate {
+    pub(crate) action: AiAction,
+    pub(crate) target_idx: UnitIdx,
+    pub(crate) cell: Vector2i,
+    pub(crate) slot_idx: usize,
+    pub(crate) score: Vec<i32>,
+}
+
+impl GodotConvert for TracedCandidate {
+    type Via = Dictionary;
+}
+
+impl ToGodot for TracedCandidate {
+    type ToVia<'v> = Dictionary;
+
+    fn to_godot(&self) -> Self::Via {
+        dict! {
+            "action": self.action as u8,
+            "target_idx": self.target_idx,
+            "cell": self.cell,
+            "slot_idx": self.slot_idx as i64,
+            "score": self.score.iter().copied().collect::<Array<i32>>(),
+        }
+    }
+}
+
+/// Record of how the planner reached the decision for a unit.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct PlanTrace {
+    pub(crate) active_keys: Vec<BehaviourKey>,
+    pub(crate) behaviour: Option<UnitBehaviour>,
+    /// Cells the unit could act from with their path cost, preferred first
+    pub(crate) cells: Vec<(Vector2i, i32)>,
+    pub(crate) candidates: Vec<TracedCandidate>,
+    /// Cells considered to move to when no action was found, with the score
+    /// used to pick the lowest one
+    pub(crate) movement: Vec<(Vector2i, Vec<i32>)>,
+    /// Rank of the plan when ordering the units of a phase
+    pub(crate) phase_rank: Option<(u8, i32)>,
+}
+
+impl GodotConvert for PlanTrace {
+    type Via = Dictionary;
+}
+
+impl ToGodot for PlanTrace {
+    type ToVia<'v> = Dictionary;
+
+    fn to_godot(&self) -> Self::Via {
+        let mut trace_dict = dict! {
+            "active_keys": self.active_keys.iter().map(|key| *key as u8).collect::<Array<u8>>(),
+            "behaviour": self.behaviour.map(|behaviour| behaviour.to_godot()).unwrap_or_default(),
+            "cells": self
+                .cells
+                .iter()
+                .map(|(cell, path_cost)| dict! { "cell": *cell, "cost": *path_cost })
+                .collect::<Array<Dictionary>>(),
+            "candidates": self
+                .candidates
+                .iter()
+                .map(TracedCandidate::to_godot)
+                .collect::<Array<Dictionary>>(),
+            "movement": self
+                .movement
+                .iter()
+                .map(|(cell, score)| dict! {
+                    "cell": *cell,
+                    "score": score.iter().copied().collect::<Array<i32>>(),
+                })
+                .collect::<Array<Dictionary>>(),
+        };
+
+        if let Some((action_rank, tie_rank)) = self.phase_rank {
+            trace_dict.set("phase_rank", varray![action_rank, tie_rank]);
+        }
+
+        trace_dict
+    }
+}
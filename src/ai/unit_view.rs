@@ -0,0 +1,127 @@
+use crate::{
+    combat::{damage_dealt, triangle_advantage},
+    database::{
+        combat_rules::CombatRules,
+        inventory::{AreaShape, WeaponColor, WeaponDamageType},
+    },
+    game_entities::unit_data::UnitIdx,
+    pathfinding::TerrainGrid,
+};
+
+use godot::prelude::*;
+
+/// Snapshot of the data of a placed unit used by the planner, taken from
+/// the point of view of the unit being planned.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct UnitView {
+    pub(crate) unit_idx: UnitIdx,
+    pub(crate) cell: Vector2i,
+    /// Whether the unit is hostile to the unit being planned
+    pub(crate) hostile: bool,
+    pub(crate) current_htp: i32,
+    pub(crate) max_htp: i32,
+    pub(crate) move_range: i32,
+    pub(crate) attack_range: Vector2i,
+    /// Power of the equipped weapon plus the stat it scales with
+    pub(crate) attack_power: i32,
+    /// Damage type of the equipped weapon, `None` if the unit has no weapon
+    pub(crate) damage_type: Option<WeaponDamageType>,
+    /// Colour of the equipped weapon, `None` if the unit has no weapon
+    pub(crate) weapon_color: Option<WeaponColor>,
+    pub(crate) def: i32,
+    pub(crate) spt: i32,
+}
+
+impl UnitView {
+    pub(crate) fn can_attack(&self) -> bool {
+        self.damage_type.is_some() && self.attack_range > Vector2i::ZERO
+    }
+
+    pub(crate) fn missing_htp(&self) -> i32 {
+        (self.max_htp - self.current_htp).max(0)
+    }
+
+    /// Current hit points as a percentage of the maximum.
+    pub(crate) fn htp_percent(&self) -> i32 {
+        if self.max_htp > 0 {
+            self.current_htp * 100 / self.max_htp
+        } else {
+            0
+        }
+    }
+
+    /// Rough estimate of the damage dealt by this unit when attacking
+    /// `defender`, standing on its current cell of `terrain_grid` and
+    /// following `rules`. Hit chances and skills are not taken into account.
+    pub(crate) fn estimated_damage(
+        &self,
+        defender: &UnitView,
+        terrain_grid: &TerrainGrid,
+        rules: &CombatRules,
+    ) -> i32 {
+        let Some(damage_type) = self.damage_type else {
+            return 0;
+        };
+
+        let triangle_damage = match (self.weapon_color, defender.weapon_color) {
+            (Some(color), Some(target_color)) => {
+                triangle_advantage(color, target_color) * rules.triangle.damage as i32
+            }
+            _ => 0,
+        };
+
+        damage_dealt(
+            self.attack_power + triangle_damage,
+            damage_type,
+            defender.def,
+            defender.spt,
+            terrain_grid.bonus_at(defender.cell).def,
+            rules,
+        )
+    }
+}
+
+/// Kind of outcome of a support or consumable effect.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum SupportKind {
+    /// Restores up to the given amount of hit points
+    Heal(i32),
+    Buff,
+    Debuff,
+}
+
+/// Side of the units a support option can be used on.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum SupportSide {
+    Oneself,
+    Friendly,
+    Hostile,
+    Any,
+}
+
+/// Support equipment or consumable the unit being planned can use.
+#[derive(Clone, Debug)]
+pub(crate) struct SupportOption {
+    pub(crate) slot_idx: usize,
+    pub(crate) kind: SupportKind,
+    pub(crate) side: SupportSide,
+    /// Distance range to the aimed unit, unused for `SupportSide::Oneself`
+    pub(crate) range: Vector2i,
+    /// Whether every unit of `side` within `range` is affected at once
+    pub(crate) every_target: bool,
+    pub(crate) area: Option<AreaShape>,
+    pub(crate) consumable: bool,
+}
+
+impl SupportOption {
+    /// Returns **true** if the option can affect `unit`, `is_self` tells
+    /// whether `unit` is the one using it.
+    pub(crate) fn can_affect(&self, unit: &UnitView, is_self: bool) -> bool {
+        match self.side {
+            SupportSide::Oneself => is_self,
+            SupportSide::Friendly => !unit.hostile && !is_self,
+            SupportSide::Hostile => unit.hostile,
+            SupportSide::Any => !is_self,
+        }
+    }
+}
@@ -0,0 +1,395 @@
+use crate::{
+    database::{
+        DbConnector,
+        combat_rules::{CombatRules, PiercingMitigation},
+        effect::{CombatStatEffect, EffectId, EffectVariant, StatEffect, UnitCombatStat, UnitStat},
+        inventory::{
+            EntryUses, EntryVariant, WeaponColor, WeaponDamageType, WeaponEntry, WeaponSlotCategory,
+        },
+        skill::{SkillTrigger, SkillTriggerCondition},
+    },
+    game_entities::unit_data::{UnitData, UnitIdx},
+    pathfinding::{TerrainBonus, TerrainGrid},
+};
+
+use super::CombatFlow;
+
+use godot::prelude::*;
+
+/// Levels of nested `EffectVariant::Parent` effects followed when gathering
+/// the effects of a skill.
+const MAX_EFFECT_DEPTH: u8 = 8;
+
+/// Hit penalty applied for every point of dexterity below the weapon's
+/// `required_dex`.
+const DEX_DEFICIT_HIT_PENALTY: i32 = 5;
+
+/// Stats of a unit taking part in a combat, with the stat effects of its
+/// skills already applied.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub(crate) struct CombatStats {
+    pub(crate) htp: i32,
+    pub(crate) str: i32,
+    pub(crate) mag: i32,
+    pub(crate) def: i32,
+    pub(crate) spt: i32,
+    pub(crate) agi: i32,
+    pub(crate) dex: i32,
+}
+
+impl CombatStats {
+    pub(crate) fn of(unit_data: &UnitData) -> Self {
+        Self {
+            htp: unit_data.get_current_max_htp() as i32,
+            str: unit_data.get_current_str() as i32,
+            mag: unit_data.get_current_mag() as i32,
+            def: unit_data.get_current_def() as i32,
+            spt: unit_data.get_current_spt() as i32,
+            agi: unit_data.get_current_agi() as i32,
+            dex: unit_data.get_current_dex() as i32,
+        }
+    }
+
+    /// Formula : `(dex * 5) + (agi * 2)`
+    pub(crate) fn base_hit(&self) -> i32 {
+        self.dex * 5 + self.agi * 2
+    }
+
+    /// Formula : `(agi * 5) + dex`
+    pub(crate) fn base_avoid(&self) -> i32 {
+        self.agi * 5 + self.dex
+    }
+
+    /// Formula : `(dex * 2) + agi`
+    pub(crate) fn base_crit(&self) -> i32 {
+        self.dex * 2 + self.agi
+    }
+
+    /// Formula : `dex + (agi * 2)`
+    pub(crate) fn base_dodge(&self) -> i32 {
+        self.dex + self.agi * 2
+    }
+
+    fn apply(&mut self, stat_effect: &StatEffect) {
+        let amount = stat_effect.amount as i32;
+
+        match stat_effect.stat {
+            UnitStat::Htp => self.htp += amount,
+            UnitStat::Str => self.str += amount,
+            UnitStat::Mag => self.mag += amount,
+            UnitStat::Def => self.def += amount,
+            UnitStat::Spt => self.spt += amount,
+            UnitStat::Agi => self.agi += amount,
+            UnitStat::Dex => self.dex += amount,
+            UnitStat::Mov => {}
+        }
+    }
+}
+
+/// Sum of the `CombatStatModifier` effects of the skills of a unit.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub(crate) struct CombatMods {
+    pub(crate) hit: i32,
+    pub(crate) avo: i32,
+    pub(crate) crit: i32,
+    pub(crate) dodge: i32,
+}
+
+impl CombatMods {
+    fn apply(&mut self, combat_stat_effect: &CombatStatEffect) {
+        let amount = combat_stat_effect.amount as i32;
+
+        match combat_stat_effect.stat {
+            UnitCombatStat::Hit => self.hit += amount,
+            UnitCombatStat::Avo => self.avo += amount,
+            UnitCombatStat::Crit => self.crit += amount,
+            UnitCombatStat::Dodge => self.dodge += amount,
+        }
+    }
+}
+
+/// Snapshot of a unit taking part in a combat, standing on `cell`.
+#[derive(Clone, Default)]
+pub(crate) struct Combatant {
+    pub(crate) unit_idx: UnitIdx,
+    pub(crate) cell: Vector2i,
+    pub(crate) current_htp: i32,
+    pub(crate) stats: CombatStats,
+    pub(crate) combat_mods: CombatMods,
+    pub(crate) flow: CombatFlow,
+    pub(crate) terrain: TerrainBonus,
+    /// `None` if the unit has no weapon to fight with
+    pub(crate) weapon: Option<WeaponEntry>,
+    /// Inventory slot holding the weapon
+    pub(crate) weapon_slot: Option<usize>,
+    pub(crate) weapon_uses: EntryUses,
+    /// Hit chance accumulated by the unit under `HitRollModel::Fixed`
+    pub(crate) hit_accumulated: i32,
+}
+
+impl Combatant {
+    /// Builds the combatant of `unit_data` standing on `cell`, fighting
+    /// with the weapon in `slot_idx` or the equipped one if `None`.
+    ///
+    /// Only skills triggered at `Passive` or `CombatStart` are applied, and
+    /// from those the ones without condition or with `HtpValue`, which holds
+    /// while the unit is below half of its hit points. Conditions depending
+    /// on the rest of the map are not evaluated.
+    ///
+    /// Returns `None` if `slot_idx` does not hold a weapon.
+    pub(crate) fn from_unit(
+        unit_data: &UnitData,
+        cell: Vector2i,
+        slot_idx: Option<usize>,
+        db: &DbConnector,
+        terrain_grid: &TerrainGrid,
+    ) -> Option<Self> {
+        let mut combatant = Self {
+            unit_idx: unit_data.unit_idx,
+            cell,
+            current_htp: unit_data.current_htp as i32,
+            stats: CombatStats::of(unit_data),
+            terrain: terrain_grid.bonus_at(cell),
+            ..Default::default()
+        };
+
+        let weapon_slot = slot_idx.or(usize::try_from(unit_data.equipped_slot_idx).ok());
+
+        if let Some(weapon_slot) = weapon_slot {
+            let weapon_entry = unit_data
+                .inventory_slots
+                .get(weapon_slot)
+                .filter(|slot| slot.contains_weapon())
+                .and_then(|slot| slot.get_entry())
+                .and_then(|entry| match &db.inventory.get(&entry.id)?._variant {
+                    EntryVariant::Weapon(weapon_entry) => Some((weapon_entry, entry.uses)),
+                    _ => None,
+                });
+
+            match weapon_entry {
+                Some((weapon_entry, weapon_uses)) => {
+                    combatant.weapon = Some(weapon_entry.clone());
+                    combatant.weapon_slot = Some(weapon_slot);
+                    combatant.weapon_uses = weapon_uses;
+                }
+                None if slot_idx.is_some() => {
+                    godot_error!(
+                        "Slot [{}] of unit [{}] does not hold a weapon!",
+                        weapon_slot,
+                        unit_data.unit_id
+                    );
+                    return None;
+                }
+                None => {}
+            }
+        }
+
+        let below_half_htp = combatant.current_htp * 2 < combatant.stats.htp;
+
+        for skill_id in unit_data.iter_skill_ids() {
+            let Some(skill_entry) = db.skills.get(skill_id) else {
+                godot_warn!("Skill [{}] not found in database!", skill_id);
+                continue;
+            };
+
+            let triggered = matches!(
+                skill_entry.trigger,
+                SkillTrigger::Passive | SkillTrigger::CombatStart
+            ) && match skill_entry.trigger_condition {
+                SkillTriggerCondition::None => true,
+                SkillTriggerCondition::HtpValue => below_half_htp,
+                _ => false,
+            };
+
+            if triggered {
+                combatant.apply_effect(&skill_entry.effect_id, db, 0);
+            }
+        }
+
+        Some(combatant)
+    }
+
+    fn apply_effect(&mut self, effect_id: &EffectId, db: &DbConnector, depth: u8) {
+        if depth > MAX_EFFECT_DEPTH {
+            godot_warn!("Effect [{}] nested too deep, ignoring it", effect_id);
+            return;
+        }
+
+        let Some(effect_entry) = db.effects.get(effect_id) else {
+            godot_warn!("Effect [{}] not found in database!", effect_id);
+            return;
+        };
+
+        match &effect_entry.variant {
+            EffectVariant::Parent(child_ids) => {
+                for child_id in child_ids {
+                    self.apply_effect(child_id, db, depth + 1);
+                }
+            }
+            EffectVariant::StatModifier(stat_effect) => self.stats.apply(stat_effect),
+            EffectVariant::CombatStatModifier(combat_stat_effect) => {
+                self.combat_mods.apply(combat_stat_effect)
+            }
+            EffectVariant::CombatFlowModifier(flow_effect) => self.flow.apply(*flow_effect),
+            _ => {}
+        }
+    }
+
+    /// Returns **true** if the combatant can strike a unit at `distance`.
+    pub(crate) fn reaches(&self, distance: i32) -> bool {
+        self.weapon.as_ref().is_some_and(|weapon| {
+            let range = weapon.range.to_godot();
+
+            range.x <= distance && distance <= range.y
+        })
+    }
+
+    /// Power of the weapon plus the stat it scales with, lowered by one for
+    /// every point of strength or magic below the weapon requirements.
+    pub(crate) fn attack_power(&self) -> i32 {
+        let Some(weapon) = self.weapon.as_ref() else {
+            return 0;
+        };
+
+        let scaling_stat = match weapon.slot_category {
+            WeaponSlotCategory::Physical => self.stats.str,
+            WeaponSlotCategory::Magical => self.stats.mag,
+        };
+        let str_deficit = (weapon.required_str as i32 - self.stats.str).max(0);
+        let mag_deficit = (weapon.required_mag as i32 - self.stats.mag).max(0);
+
+        weapon.power as i32 + scaling_stat - str_deficit - mag_deficit
+    }
+
+    pub(crate) fn hit(&self) -> i32 {
+        let weapon_hit = self.weapon.as_ref().map_or(0, |weapon| {
+            let dex_deficit = (weapon.required_dex as i32 - self.stats.dex).max(0);
+
+            weapon.hit_mod as i32 - dex_deficit * DEX_DEFICIT_HIT_PENALTY
+        });
+
+        self.stats.base_hit() + weapon_hit + self.combat_mods.hit
+    }
+
+    pub(crate) fn avoid(&self) -> i32 {
+        let weapon_avo = self
+            .weapon
+            .as_ref()
+            .map_or(0, |weapon| weapon.avo_mod as i32);
+
+        self.stats.base_avoid() + weapon_avo + self.terrain.avo + self.combat_mods.avo
+    }
+
+    pub(crate) fn crit(&self) -> i32 {
+        let weapon_crit = self
+            .weapon
+            .as_ref()
+            .map_or(0, |weapon| weapon.crit_mod as i32);
+
+        self.stats.base_crit() + weapon_crit + self.combat_mods.crit
+    }
+
+    pub(crate) fn dodge(&self) -> i32 {
+        let weapon_dodge = self
+            .weapon
+            .as_ref()
+            .map_or(0, |weapon| weapon.dodge_mod as i32);
+
+        self.stats.base_dodge() + weapon_dodge + self.combat_mods.dodge
+    }
+
+    /// Agility of the combatant, lowered by the weight of its weapon if
+    /// `rules` take it into account.
+    pub(crate) fn attack_speed(&self, rules: &CombatRules) -> i32 {
+        let weapon_burden = self
+            .weapon
+            .as_ref()
+            .filter(|_| rules.follow_up.weapon_weight)
+            .map_or(0, |weapon| {
+                (weapon.required_str as i32 - self.stats.str).max(0)
+            });
+
+        self.stats.agi - weapon_burden
+    }
+
+    /// Returns **true** if the combatant is fast enough to attack `target`
+    /// a second time following `rules`.
+    pub(crate) fn follows_up_on(&self, target: &Combatant, rules: &CombatRules) -> bool {
+        let speed_difference = rules.follow_up.speed_difference as i32;
+
+        speed_difference > 0
+            && self.attack_speed(rules) - target.attack_speed(rules) >= speed_difference
+    }
+
+    /// Returns `1` if the weapon of the combatant has colour advantage over
+    /// the weapon of `target`, `-1` if it is at a disadvantage and `0`
+    /// otherwise.
+    pub(crate) fn triangle_advantage(&self, target: &Combatant) -> i32 {
+        let (Some(weapon), Some(target_weapon)) = (self.weapon.as_ref(), target.weapon.as_ref())
+        else {
+            return 0;
+        };
+
+        triangle_advantage(weapon.color, target_weapon.color)
+    }
+
+    /// Hit chance of the combatant against `target` before its avoid.
+    pub(crate) fn hit_on(&self, target: &Combatant, rules: &CombatRules) -> i32 {
+        self.hit() + self.triangle_advantage(target) * rules.triangle.hit as i32
+    }
+
+    /// Damage dealt by each strike of the combatant on `target`.
+    pub(crate) fn damage_on(&self, target: &Combatant, rules: &CombatRules) -> i32 {
+        let Some(weapon) = self.weapon.as_ref() else {
+            return 0;
+        };
+
+        let triangle_damage = self.triangle_advantage(target) * rules.triangle.damage as i32;
+
+        damage_dealt(
+            self.attack_power() + triangle_damage,
+            weapon.damage_type,
+            target.stats.def,
+            target.stats.spt,
+            target.terrain.def,
+            rules,
+        )
+    }
+}
+
+/// Returns `1` if a weapon of `color` has colour advantage over one of
+/// `target_color`, `-1` if it is at a disadvantage and `0` otherwise.
+pub(crate) fn triangle_advantage(color: WeaponColor, target_color: WeaponColor) -> i32 {
+    match (color, target_color) {
+        (WeaponColor::Red, WeaponColor::Green)
+        | (WeaponColor::Green, WeaponColor::Blue)
+        | (WeaponColor::Blue, WeaponColor::Red) => 1,
+        (WeaponColor::Green, WeaponColor::Red)
+        | (WeaponColor::Blue, WeaponColor::Green)
+        | (WeaponColor::Red, WeaponColor::Blue) => -1,
+        _ => 0,
+    }
+}
+
+/// Damage dealt by an attack of `attack_power` and `damage_type` on a unit
+/// with `def` and `spt` standing on a terrain granting `terrain_def`.
+pub(crate) fn damage_dealt(
+    attack_power: i32,
+    damage_type: WeaponDamageType,
+    def: i32,
+    spt: i32,
+    terrain_def: i32,
+    rules: &CombatRules,
+) -> i32 {
+    let defense = match damage_type {
+        WeaponDamageType::Physical => def,
+        WeaponDamageType::Magical => spt,
+        WeaponDamageType::Piercing => match rules.piercing {
+            PiercingMitigation::Lowest => def.min(spt),
+            PiercingMitigation::Halved => def.min(spt) / 2,
+            PiercingMitigation::Ignored => 0,
+        },
+    } + terrain_def;
+
+    (attack_power - defense).max(0)
+}
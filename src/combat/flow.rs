@@ -0,0 +1,108 @@
+use crate::database::effect::CombatFlowEffect;
+
+/// Side of a combat, the `Attacker` is the unit that started it.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum CombatSide {
+    Attacker = 0,
+    Defender = 1,
+}
+
+impl CombatSide {
+    pub(crate) fn opponent(self) -> Self {
+        match self {
+            CombatSide::Attacker => CombatSide::Defender,
+            CombatSide::Defender => CombatSide::Attacker,
+        }
+    }
+}
+
+/// `CombatFlowModifier` effects of the skills of a combatant.
+///
+/// When the effects of both sides conflict they apply in this order:
+/// 1. `CancelCounter` of the attacker leaves the defender without strikes,
+///    even if it has `AttackFirst`.
+/// 2. `NoSecondWind` limits the opponent to a single strike, overriding its
+///    `BraveStrike` and follow-ups.
+/// 3. `AttackFirst` of the defender makes it strike first, unless the
+///    attacker has it too.
+/// 4. `Desperation` of the attacker makes all its attacks land before the
+///    defender strikes back.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub(crate) struct CombatFlow {
+    pub(crate) attack_first: bool,
+    pub(crate) no_second_wind: bool,
+    pub(crate) brave_strike: bool,
+    pub(crate) desperation: bool,
+    pub(crate) cancel_counter: bool,
+}
+
+impl CombatFlow {
+    pub(crate) fn apply(&mut self, flow_effect: CombatFlowEffect) {
+        match flow_effect {
+            CombatFlowEffect::AttackFirst => self.attack_first = true,
+            CombatFlowEffect::NoSecondWind => self.no_second_wind = true,
+            CombatFlowEffect::BraveStrike => self.brave_strike = true,
+            CombatFlowEffect::Desperation => self.desperation = true,
+            CombatFlowEffect::CancelCounter => self.cancel_counter = true,
+        }
+    }
+
+    /// Order of the strikes of a combat between an attacker with `attacker`
+    /// flow and a defender with `defender` flow, where each side gets the
+    /// number of `attacks` at its `CombatSide` index.
+    pub(crate) fn strike_order(
+        attacker: &CombatFlow,
+        defender: &CombatFlow,
+        attacks: [u8; 2],
+    ) -> Vec<CombatSide> {
+        let attacker_idx = CombatSide::Attacker as usize;
+        let defender_idx = CombatSide::Defender as usize;
+
+        let mut attacks_left = attacks;
+        let mut strikes_per_attack = [1; 2];
+
+        if attacker.cancel_counter {
+            attacks_left[defender_idx] = 0;
+        }
+
+        if attacker.brave_strike {
+            strikes_per_attack[attacker_idx] = 2;
+        }
+
+        for (flow, opponent_idx) in [(attacker, defender_idx), (defender, attacker_idx)] {
+            if flow.no_second_wind {
+                attacks_left[opponent_idx] = attacks_left[opponent_idx].min(1);
+                strikes_per_attack[opponent_idx] = 1;
+            }
+        }
+
+        let mut side = if defender.attack_first && !attacker.attack_first {
+            CombatSide::Defender
+        } else {
+            CombatSide::Attacker
+        };
+        let mut order = Vec::new();
+
+        while attacks_left.iter().any(|side_attacks| *side_attacks > 0) {
+            let side_idx = side as usize;
+
+            if attacks_left[side_idx] > 0 {
+                let side_attacks = if side == CombatSide::Attacker && attacker.desperation {
+                    attacks_left[side_idx]
+                } else {
+                    1
+                };
+                attacks_left[side_idx] -= side_attacks;
+
+                order.extend(std::iter::repeat_n(
+                    side,
+                    (side_attacks * strikes_per_attack[side_idx]) as usize,
+                ));
+            }
+
+            side = side.opponent();
+        }
+
+        order
+    }
+}
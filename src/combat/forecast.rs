@@ -0,0 +1,137 @@
+use super::{CombatFlow, CombatSide, Combatant};
+use crate::database::{combat_rules::CombatRules, difficulty::HitRollModel};
+
+use godot::prelude::*;
+
+/// Expected outcome of the strikes of one side of a combat.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub(crate) struct StrikeForecast {
+    /// Damage dealt by each strike that lands
+    pub(crate) damage: i32,
+    /// Displayed hit chance, from 0 to 100
+    pub(crate) hit: i32,
+    /// Actual chance of the first strike landing under the `HitRollModel`
+    /// of the combat, from 0 to 100
+    pub(crate) effective_hit: i32,
+    /// Displayed critical chance, from 0 to 100
+    pub(crate) crit: i32,
+    /// Number of strikes, 0 if the side cannot strike
+    pub(crate) strikes: u8,
+    /// Whether the side attacks a second time after both sides attacked
+    pub(crate) follow_up: bool,
+}
+
+impl StrikeForecast {
+    fn of(
+        striker: &Combatant,
+        target: &Combatant,
+        distance: i32,
+        rules: &CombatRules,
+        hit_roll: HitRollModel,
+    ) -> Self {
+        if !striker.reaches(distance) {
+            return Self::default();
+        }
+
+        let hit = (striker.hit_on(target, rules) - target.avoid()).clamp(0, 100);
+
+        Self {
+            damage: striker.damage_on(target, rules),
+            hit,
+            effective_hit: hit_roll.effective_hit(hit, striker.hit_accumulated),
+            crit: (striker.crit() - target.dodge()).clamp(0, 100),
+            strikes: 0,
+            follow_up: striker.follows_up_on(target, rules) && !target.flow.no_second_wind,
+        }
+    }
+}
+
+impl GodotConvert for StrikeForecast {
+    type Via = Dictionary;
+}
+
+impl ToGodot for StrikeForecast {
+    type ToVia<'v> = Dictionary;
+
+    fn to_godot(&self) -> Self::Via {
+        dict! {
+            "damage": self.damage,
+            "hit": self.hit,
+            "effective_hit": self.effective_hit,
+            "crit": self.crit,
+            "strikes": self.strikes,
+            "follow_up": self.follow_up,
+        }
+    }
+}
+
+/// Expected outcome of a combat started by `attacker` on `defender`.
+#[derive(Clone, Default, PartialEq, Eq, Debug)]
+pub(crate) struct CombatForecast {
+    pub(crate) attacker: StrikeForecast,
+    pub(crate) defender: StrikeForecast,
+    /// Side of each strike of the combat, in order
+    pub(crate) order: Vec<CombatSide>,
+}
+
+impl CombatForecast {
+    /// Forecasts the combat between `attacker` and `defender` standing
+    /// `distance` cells away from each other, following `rules` and rolling
+    /// strikes with `hit_roll`.
+    pub(crate) fn between(
+        attacker: &Combatant,
+        defender: &Combatant,
+        distance: i32,
+        rules: &CombatRules,
+        hit_roll: HitRollModel,
+    ) -> Self {
+        if !attacker.reaches(distance) {
+            return Self::default();
+        }
+
+        let mut forecast = Self {
+            attacker: StrikeForecast::of(attacker, defender, distance, rules, hit_roll),
+            defender: StrikeForecast::of(defender, attacker, distance, rules, hit_roll),
+            order: Vec::new(),
+        };
+
+        let attacks = [
+            1 + forecast.attacker.follow_up as u8,
+            (1 + forecast.defender.follow_up as u8) * defender.reaches(distance) as u8,
+        ];
+        forecast.order = CombatFlow::strike_order(&attacker.flow, &defender.flow, attacks);
+
+        for side in forecast.order.iter() {
+            match side {
+                CombatSide::Attacker => forecast.attacker.strikes += 1,
+                CombatSide::Defender => forecast.defender.strikes += 1,
+            }
+        }
+
+        if forecast.defender.strikes == 0 {
+            forecast.defender = StrikeForecast::default();
+        }
+
+        forecast
+    }
+}
+
+impl GodotConvert for CombatForecast {
+    type Via = Dictionary;
+}
+
+impl ToGodot for CombatForecast {
+    type ToVia<'v> = Dictionary;
+
+    fn to_godot(&self) -> Self::Via {
+        dict! {
+            "attacker": self.attacker.to_godot(),
+            "defender": self.defender.to_godot(),
+            "order": self
+                .order
+                .iter()
+                .map(|side| *side as u8)
+                .collect::<Array<u8>>(),
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use super::CombatSide;
+use crate::{
+    database::difficulty::HitRollModel,
+    game_entities::{battle_rng::BattleRng, unit_data::UnitIdx},
+};
+
+/// Hit chance accumulated by each unit before its first strike of the
+/// battle under `HitRollModel::Fixed`, so chances of 50 or more land it.
+pub(crate) const FIXED_HIT_START: i32 = 50;
+
+impl HitRollModel {
+    /// Actual chance, from 0 to 100, of the first strike with the displayed
+    /// `hit` chance landing, by a unit that `accumulated` hit chance from
+    /// its previous strikes under `Fixed`.
+    pub(crate) fn effective_hit(self, hit: i32, accumulated: i32) -> i32 {
+        let hit = hit.clamp(0, 100);
+
+        match self {
+            HitRollModel::OneRoll => hit,
+            HitRollModel::TwoRolls => {
+                // Pairs of rolls from 0 to 99 whose sum is below `2 * hit`,
+                // out of the 10000 possible pairs.
+                let landing_pairs = (0..2 * hit)
+                    .map(|sum| if sum < 100 { sum + 1 } else { 199 - sum })
+                    .sum::<i32>();
+
+                (landing_pairs + 50) / 100
+            }
+            HitRollModel::Fixed => {
+                if accumulated + hit >= 100 {
+                    100
+                } else {
+                    0
+                }
+            }
+        }
+    }
+}
+
+/// Decides whether the strikes of a combat land following a `HitRollModel`.
+pub(crate) struct HitRoller {
+    model: HitRollModel,
+    /// Unit idx of each side, whose accumulated hit chance is kept by the
+    /// `BattleRng` under `Fixed`
+    unit_idxs: [UnitIdx; 2],
+}
+
+impl HitRoller {
+    pub(crate) fn new(model: HitRollModel, unit_idxs: [UnitIdx; 2]) -> Self {
+        Self { model, unit_idxs }
+    }
+
+    /// Returns **true** if a strike of `side` with the displayed `hit`
+    /// chance lands.
+    pub(crate) fn roll(&self, side: CombatSide, hit: i32, rng: &mut BattleRng) -> bool {
+        match self.model {
+            HitRollModel::OneRoll => rng.roll_percent() < hit,
+            HitRollModel::TwoRolls => (rng.roll_percent() + rng.roll_percent()) / 2 < hit,
+            HitRollModel::Fixed => rng.accumulate_hit(self.unit_idxs[side as usize], hit),
+        }
+    }
+}
@@ -0,0 +1,850 @@
+use crate::{
+    database::{
+        DbConnector,
+        combat_rules::{CombatRules, CombatRulesId},
+        difficulty::{DifficultyId, HitRollModel},
+    },
+    game_entities::{battle_rng::BattleRng, unit_data::UnitData},
+    pathfinding::TerrainGrid,
+};
+
+use godot::prelude::*;
+
+mod combatant;
+mod flow;
+mod forecast;
+mod hit_roll;
+mod resolution;
+
+pub(crate) use combatant::*;
+pub(crate) use flow::*;
+pub(crate) use forecast::*;
+pub(crate) use hit_roll::*;
+pub(crate) use resolution::*;
+
+/// Computes the outcome of combats between units placed on the map of
+/// `terrain_grid`, rolling them with the `rng` of the battle.
+#[derive(GodotClass)]
+#[class(no_init, base=RefCounted)]
+pub(crate) struct CombatCalculator {
+    db: Gd<DbConnector>,
+    terrain_grid: Gd<TerrainGrid>,
+    rng: Gd<BattleRng>,
+    rules: CombatRules,
+    hit_roll: HitRollModel,
+}
+
+#[godot_api]
+impl CombatCalculator {
+    #[func]
+    fn with_grid(
+        db: Gd<DbConnector>,
+        terrain_grid: Gd<TerrainGrid>,
+        rng: Gd<BattleRng>,
+    ) -> Gd<Self> {
+        Gd::from_object(Self {
+            db,
+            terrain_grid,
+            rng,
+            rules: CombatRules::default(),
+            hit_roll: HitRollModel::default(),
+        })
+    }
+
+    /// Rolls strikes following the `HitRollModel` of the difficulty
+    /// `difficulty_id`, an empty identifier restores the default model.
+    ///
+    /// Returns **false** if the difficulty could not be found.
+    #[func]
+    fn set_difficulty(&mut self, difficulty_id: DifficultyId) -> bool {
+        if difficulty_id.is_empty() {
+            self.hit_roll = HitRollModel::default();
+            return true;
+        }
+
+        let Some(hit_roll) = self
+            .db
+            .bind()
+            .difficulties
+            .get(&difficulty_id)
+            .map(|entry| entry.profile.hit_roll)
+        else {
+            godot_error!("Difficulty [{}] not found in database!", difficulty_id);
+            return false;
+        };
+
+        self.hit_roll = hit_roll;
+
+        true
+    }
+
+    /// Overrides the `HitRollModel` used to roll strikes, for games where it
+    /// is chosen as an option instead of following the difficulty.
+    #[func]
+    fn set_hit_roll(&mut self, hit_roll: HitRollModel) {
+        self.hit_roll = hit_roll;
+    }
+
+    #[func]
+    fn get_hit_roll(&self) -> HitRollModel {
+        self.hit_roll
+    }
+
+    /// Replaces the generator combats are rolled with, after loading a
+    /// battle with `BattleRng.from_save_point`.
+    #[func]
+    fn set_rng(&mut self, rng: Gd<BattleRng>) {
+        self.rng = rng;
+    }
+
+    /// Returns the generator combats are rolled with, whose `get_save_data`
+    /// has to be written to the save point after every `resolve_combat`.
+    #[func]
+    fn get_rng(&self) -> Gd<BattleRng> {
+        self.rng.clone()
+    }
+
+    /// Follows the combat rules `combat_rules_id` in every calculation, an
+    /// empty identifier restores the default rules.
+    ///
+    /// Returns **false** if the combat rules could not be found.
+    #[func]
+    fn set_rules(&mut self, combat_rules_id: CombatRulesId) -> bool {
+        if combat_rules_id.is_empty() {
+            self.rules = CombatRules::default();
+            return true;
+        }
+
+        let Some(rules_entry) = self
+            .db
+            .bind()
+            .combat_rules
+            .get(&combat_rules_id)
+            .map(|entry| entry.rules)
+        else {
+            godot_error!("Combat rules [{}] not found in database!", combat_rules_id);
+            return false;
+        };
+
+        self.rules = rules_entry;
+
+        true
+    }
+
+    /// Forecasts the combat started by `attacker` standing on
+    /// `attacker_cell` on `defender` standing on `defender_cell`. Each unit
+    /// fights with the weapon in its `*_slot`, or the equipped one if `-1`.
+    ///
+    /// # Returns
+    ///
+    /// A dictionary with the following keys:
+    /// * `order`: Array with the side of each strike in order, `0` for the
+    ///   attacker and `1` for the defender.
+    ///
+    /// And the keys `attacker` and `defender`, each holding:
+    /// * `damage`: Damage dealt by each strike that lands.
+    /// * `hit`: Displayed hit chance.
+    /// * `effective_hit`: Actual chance of the first strike landing under
+    ///   the current `HitRollModel`, from the hit chance accumulated by the
+    ///   unit in previous combats under `Fixed`.
+    /// * `crit`: Displayed critical chance.
+    /// * `strikes`: Number of strikes, 0 if the unit cannot strike.
+    /// * `follow_up`: Whether the unit attacks a second time.
+    ///
+    /// An empty dictionary is returned if either slot does not hold a weapon
+    /// or the attacker has no weapon.
+    #[func]
+    fn forecast(
+        &self,
+        attacker: Gd<UnitData>,
+        attacker_cell: Vector2i,
+        attacker_slot: i32,
+        defender: Gd<UnitData>,
+        defender_cell: Vector2i,
+        defender_slot: i32,
+    ) -> Dictionary {
+        let Some((attacker, defender)) = self.combatants(
+            &attacker.bind(),
+            attacker_cell,
+            attacker_slot,
+            &defender.bind(),
+            defender_cell,
+            defender_slot,
+        ) else {
+            return Dictionary::new();
+        };
+
+        self.forecast_between(&attacker, &defender).to_godot()
+    }
+
+    /// Resolves the combat started by `attacker` standing on `attacker_cell`
+    /// on `defender` standing on `defender_cell`, both fighting with their
+    /// equipped weapons. Hits and criticals are rolled with the generator of
+    /// the calculator, and the resulting hit points are applied to both
+    /// units. Each strike consumes a use of the weapon of the striking unit,
+    /// weapons running out of uses are removed from its inventory.
+    ///
+    /// Resolving a combat advances the generator, the `get_save_data` of
+    /// `get_rng` has to be written to the save point afterwards, or
+    /// reloading the battle would roll the same strikes again.
+    ///
+    /// # Returns
+    ///
+    /// A dictionary with the following keys:
+    /// * `strikes`: Array with the strikes in order, each holding:
+    ///     * `side`: `0` if struck by the attacker, `1` by the defender.
+    ///     * `hit`: Whether the strike landed.
+    ///     * `crit`: Whether the strike was a critical.
+    ///     * `damage`: Damage dealt.
+    ///     * `target_htp`: Hit points of the struck unit afterwards.
+    /// * `attacker_htp`: Hit points of the attacker after the combat.
+    /// * `defender_htp`: Hit points of the defender after the combat.
+    /// * `attacker_broken_idx`: Item index of the weapon of the attacker if
+    ///   it broke. Otherwise -1.
+    /// * `defender_broken_idx`: Item index of the weapon of the defender if
+    ///   it broke. Otherwise -1.
+    ///
+    /// An empty dictionary is returned if the attacker has no weapon.
+    #[func]
+    fn resolve_combat(
+        &self,
+        mut attacker: Gd<UnitData>,
+        attacker_cell: Vector2i,
+        mut defender: Gd<UnitData>,
+        defender_cell: Vector2i,
+    ) -> Dictionary {
+        if attacker == defender {
+            godot_error!("Tried to resolve a combat of a unit against itself!");
+            return Dictionary::new();
+        }
+
+        let Some((attacker_combatant, defender_combatant)) = self.combatants(
+            &attacker.bind(),
+            attacker_cell,
+            -1,
+            &defender.bind(),
+            defender_cell,
+            -1,
+        ) else {
+            return Dictionary::new();
+        };
+
+        let forecast = self.forecast_between(&attacker_combatant, &defender_combatant);
+        let outcome = CombatOutcome::resolve(
+            &forecast,
+            attacker_combatant.current_htp,
+            defender_combatant.current_htp,
+            [attacker_combatant.unit_idx, defender_combatant.unit_idx],
+            [
+                attacker_combatant.weapon_uses,
+                defender_combatant.weapon_uses,
+            ],
+            self.hit_roll,
+            &mut self.rng.clone().bind_mut(),
+        );
+
+        let mut outcome_dict = outcome.to_godot();
+
+        for (unit, combatant, side, htp, broken_key) in [
+            (
+                &mut attacker,
+                &attacker_combatant,
+                CombatSide::Attacker,
+                outcome.attacker_htp,
+                "attacker_broken_idx",
+            ),
+            (
+                &mut defender,
+                &defender_combatant,
+                CombatSide::Defender,
+                outcome.defender_htp,
+                "defender_broken_idx",
+            ),
+        ] {
+            let mut unit = unit.bind_mut();
+            unit.current_htp = htp.max(0) as u8;
+
+            let uses = u8::try_from(outcome.strikes_of(side)).unwrap_or(u8::MAX);
+            let broken_idx = combatant
+                .weapon_slot
+                .filter(|_| uses > 0)
+                .and_then(|weapon_slot| unit.consume_slot_uses(weapon_slot, uses, self.db.bind()));
+
+            outcome_dict.set(broken_key, broken_idx.map_or(-1, i64::from));
+        }
+
+        outcome_dict
+    }
+}
+
+impl CombatCalculator {
+    fn forecast_between(&self, attacker: &Combatant, defender: &Combatant) -> CombatForecast {
+        let distance = self
+            .terrain_grid
+            .bind()
+            .topology
+            .distance(attacker.cell, defender.cell);
+
+        CombatForecast::between(attacker, defender, distance, &self.rules, self.hit_roll)
+    }
+
+    fn combatants(
+        &self,
+        attacker: &UnitData,
+        attacker_cell: Vector2i,
+        attacker_slot: i32,
+        defender: &UnitData,
+        defender_cell: Vector2i,
+        defender_slot: i32,
+    ) -> Option<(Combatant, Combatant)> {
+        let db = self.db.bind();
+        let terrain_grid = self.terrain_grid.bind();
+
+        let mut attacker = Combatant::from_unit(
+            attacker,
+            attacker_cell,
+            usize::try_from(attacker_slot).ok(),
+            &db,
+            &terrain_grid,
+        )?;
+        let mut defender = Combatant::from_unit(
+            defender,
+            defender_cell,
+            usize::try_from(defender_slot).ok(),
+            &db,
+            &terrain_grid,
+        )?;
+
+        if attacker.weapon.is_none() {
+            godot_error!("Attacking unit has no weapon to fight with!");
+            return None;
+        }
+
+        let rng = self.rng.bind();
+        attacker.hit_accumulated = rng.hit_accumulated(attacker.unit_idx);
+        defender.hit_accumulated = rng.hit_accumulated(defender.unit_idx);
+
+        Some((attacker, defender))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        database::combat_rules::{PiercingMitigation, TriangleBonus},
+        database::{
+            chapter::Vector2u8,
+            inventory::{
+                WeaponCategory, WeaponColor, WeaponDamageType, WeaponEntry, WeaponSlotCategory,
+            },
+        },
+        pathfinding::TerrainBonus,
+    };
+
+    fn weapon(damage_type: WeaponDamageType, range: (u8, u8)) -> WeaponEntry {
+        WeaponEntry {
+            category: WeaponCategory::Sword,
+            color: WeaponColor::Red,
+            damage_type,
+            slot_category: WeaponSlotCategory::Physical,
+            range: Vector2u8 {
+                x: range.0,
+                y: range.1,
+            },
+            power: 5,
+            hit_mod: 10,
+            avo_mod: 0,
+            crit_mod: 0,
+            dodge_mod: 0,
+            required_str: 0,
+            required_mag: 0,
+            required_dex: 0,
+            area: None,
+        }
+    }
+
+    fn combatant(weapon: Option<WeaponEntry>) -> Combatant {
+        Combatant {
+            current_htp: 20,
+            stats: CombatStats {
+                htp: 20,
+                str: 6,
+                mag: 2,
+                def: 4,
+                spt: 1,
+                agi: 5,
+                dex: 8,
+            },
+            weapon,
+            ..Default::default()
+        }
+    }
+
+    mod forecast {
+        use super::*;
+
+        #[test]
+        fn both_sides_strike_within_range() {
+            let attacker = combatant(Some(weapon(WeaponDamageType::Physical, (1, 1))));
+            let defender = combatant(Some(weapon(WeaponDamageType::Magical, (1, 2))));
+
+            let forecast = CombatForecast::between(
+                &attacker,
+                &defender,
+                1,
+                &CombatRules::default(),
+                HitRollModel::OneRoll,
+            );
+
+            // 5 power + 6 str - 4 def
+            assert_eq!(forecast.attacker.damage, 7);
+            // 5 power + 6 str - 1 spt
+            assert_eq!(forecast.defender.damage, 10);
+            // 8 * 5 + 5 * 2 + 10 hit_mod - (5 * 5 + 8)
+            assert_eq!(forecast.attacker.hit, 27);
+            // 8 * 2 + 5 - (8 + 5 * 2)
+            assert_eq!(forecast.attacker.crit, 3);
+            assert_eq!(forecast.attacker.strikes, 1);
+            assert_eq!(forecast.defender.strikes, 1);
+        }
+
+        #[test]
+        fn defender_out_of_range_cannot_strike() {
+            let attacker = combatant(Some(weapon(WeaponDamageType::Physical, (1, 2))));
+            let defender = combatant(Some(weapon(WeaponDamageType::Physical, (1, 1))));
+
+            let forecast = CombatForecast::between(
+                &attacker,
+                &defender,
+                2,
+                &CombatRules::default(),
+                HitRollModel::OneRoll,
+            );
+
+            assert_eq!(forecast.attacker.strikes, 1);
+            assert_eq!(forecast.defender, StrikeForecast::default());
+
+            let unarmed = combatant(None);
+            let forecast = CombatForecast::between(
+                &attacker,
+                &unarmed,
+                1,
+                &CombatRules::default(),
+                HitRollModel::OneRoll,
+            );
+
+            assert_eq!(forecast.defender.strikes, 0);
+        }
+
+        #[test]
+        fn requirements_and_terrain_lower_the_outcome() {
+            let mut heavy_weapon = weapon(WeaponDamageType::Piercing, (1, 1));
+            heavy_weapon.required_str = 8;
+            heavy_weapon.required_dex = 10;
+
+            let attacker = combatant(Some(heavy_weapon));
+            let mut defender = combatant(None);
+            defender.terrain = TerrainBonus { avo: 10, def: 1 };
+
+            let forecast = CombatForecast::between(
+                &attacker,
+                &defender,
+                1,
+                &CombatRules::default(),
+                HitRollModel::OneRoll,
+            );
+
+            // 5 power + 6 str - 2 str deficit - (1 spt + 1 terrain def)
+            assert_eq!(forecast.attacker.damage, 7);
+            // 60 hit - 10 dex deficit penalty - (33 avoid + 10 terrain avo)
+            assert_eq!(forecast.attacker.hit, 7);
+        }
+
+        #[test]
+        fn faster_units_follow_up() {
+            let mut attacker = combatant(Some(weapon(WeaponDamageType::Physical, (1, 1))));
+            attacker.stats.agi = 9;
+            let defender = combatant(Some(weapon(WeaponDamageType::Physical, (1, 1))));
+
+            let forecast = CombatForecast::between(
+                &attacker,
+                &defender,
+                1,
+                &CombatRules::default(),
+                HitRollModel::OneRoll,
+            );
+
+            assert!(forecast.attacker.follow_up);
+            assert_eq!(forecast.attacker.strikes, 2);
+            assert_eq!(
+                forecast.order,
+                vec![
+                    CombatSide::Attacker,
+                    CombatSide::Defender,
+                    CombatSide::Attacker
+                ]
+            );
+
+            // 8 required str against 6 str slows the attacker down by 2.
+            let mut heavy_weapon = weapon(WeaponDamageType::Physical, (1, 1));
+            heavy_weapon.required_str = 8;
+            attacker.weapon = Some(heavy_weapon);
+
+            let forecast = CombatForecast::between(
+                &attacker,
+                &defender,
+                1,
+                &CombatRules::default(),
+                HitRollModel::OneRoll,
+            );
+
+            assert!(!forecast.attacker.follow_up);
+            assert_eq!(forecast.attacker.strikes, 1);
+        }
+
+        #[test]
+        fn no_second_wind_prevents_follow_ups() {
+            let mut attacker = combatant(Some(weapon(WeaponDamageType::Physical, (1, 1))));
+            attacker.stats.agi = 9;
+            let mut defender = combatant(Some(weapon(WeaponDamageType::Physical, (1, 1))));
+            defender.flow.no_second_wind = true;
+
+            let forecast = CombatForecast::between(
+                &attacker,
+                &defender,
+                1,
+                &CombatRules::default(),
+                HitRollModel::OneRoll,
+            );
+
+            assert!(!forecast.attacker.follow_up);
+            assert_eq!(
+                forecast.order,
+                vec![CombatSide::Attacker, CombatSide::Defender]
+            );
+        }
+    }
+
+    mod rules {
+        use super::*;
+
+        #[test]
+        fn triangle_favours_the_advantaged_colour() {
+            let rules = CombatRules {
+                triangle: TriangleBonus { hit: 15, damage: 2 },
+                ..Default::default()
+            };
+
+            let mut red_weapon = weapon(WeaponDamageType::Physical, (1, 1));
+            red_weapon.color = WeaponColor::Red;
+            let mut green_weapon = weapon(WeaponDamageType::Physical, (1, 1));
+            green_weapon.color = WeaponColor::Green;
+
+            let attacker = combatant(Some(red_weapon));
+            let defender = combatant(Some(green_weapon));
+
+            let neutral = CombatForecast::between(
+                &attacker,
+                &defender,
+                1,
+                &Default::default(),
+                HitRollModel::OneRoll,
+            );
+            let forecast =
+                CombatForecast::between(&attacker, &defender, 1, &rules, HitRollModel::OneRoll);
+
+            assert_eq!(forecast.attacker.damage, neutral.attacker.damage + 2);
+            assert_eq!(forecast.attacker.hit, neutral.attacker.hit + 15);
+            assert_eq!(forecast.defender.damage, neutral.defender.damage - 2);
+            assert_eq!(forecast.defender.hit, neutral.defender.hit - 15);
+        }
+
+        #[test]
+        fn piercing_mitigation_lowers_the_defense() {
+            let attacker = combatant(Some(weapon(WeaponDamageType::Piercing, (1, 1))));
+            let mut defender = combatant(None);
+            defender.stats.def = 6;
+            defender.stats.spt = 4;
+
+            let damage_with = |piercing| {
+                let rules = CombatRules {
+                    piercing,
+                    ..Default::default()
+                };
+
+                CombatForecast::between(&attacker, &defender, 1, &rules, HitRollModel::OneRoll)
+                    .attacker
+                    .damage
+            };
+
+            // 11 attack power against the 4 spt of the defender
+            assert_eq!(damage_with(PiercingMitigation::Lowest), 7);
+            assert_eq!(damage_with(PiercingMitigation::Halved), 9);
+            assert_eq!(damage_with(PiercingMitigation::Ignored), 11);
+        }
+    }
+
+    mod resolution {
+        use super::*;
+        use crate::{database::inventory::EntryUses, game_entities::unit_data::UnitIdx};
+
+        const UNIT_IDXS: [UnitIdx; 2] = [0, 1];
+        const INFINITE_USES: [EntryUses; 2] = [EntryUses::Infinite; 2];
+
+        fn forecast(attacker_hit: i32, defender_hit: i32) -> CombatForecast {
+            CombatForecast {
+                attacker: StrikeForecast {
+                    damage: 6,
+                    hit: attacker_hit,
+                    effective_hit: attacker_hit,
+                    crit: 0,
+                    strikes: 2,
+                    follow_up: true,
+                },
+                defender: StrikeForecast {
+                    damage: 4,
+                    hit: defender_hit,
+                    effective_hit: defender_hit,
+                    crit: 0,
+                    strikes: 1,
+                    follow_up: false,
+                },
+                order: CombatFlow::strike_order(&Default::default(), &Default::default(), [2, 1]),
+            }
+        }
+
+        #[test]
+        fn sides_take_turns_until_out_of_strikes() {
+            let mut rng = BattleRng::new(7);
+            let outcome = CombatOutcome::resolve(
+                &forecast(100, 0),
+                20,
+                20,
+                UNIT_IDXS,
+                INFINITE_USES,
+                HitRollModel::OneRoll,
+                &mut rng,
+            );
+
+            let sides = outcome
+                .strikes
+                .iter()
+                .map(|strike| strike.side)
+                .collect::<Vec<_>>();
+
+            assert_eq!(
+                sides,
+                vec![
+                    CombatSide::Attacker,
+                    CombatSide::Defender,
+                    CombatSide::Attacker
+                ]
+            );
+            assert_eq!(outcome.attacker_htp, 20);
+            assert_eq!(outcome.defender_htp, 8);
+        }
+
+        #[test]
+        fn combat_ends_when_a_side_is_defeated() {
+            let mut rng = BattleRng::new(7);
+            let outcome = CombatOutcome::resolve(
+                &forecast(100, 100),
+                20,
+                5,
+                UNIT_IDXS,
+                INFINITE_USES,
+                HitRollModel::OneRoll,
+                &mut rng,
+            );
+
+            assert_eq!(outcome.strikes.len(), 1);
+            assert_eq!(outcome.strikes[0].damage, 5);
+            assert_eq!(outcome.defender_htp, 0);
+        }
+
+        #[test]
+        fn same_state_rolls_the_same_outcome() {
+            let mut rng = BattleRng::new(42);
+            let mut other_rng = rng.clone();
+
+            let outcome = CombatOutcome::resolve(
+                &forecast(50, 50),
+                20,
+                20,
+                UNIT_IDXS,
+                INFINITE_USES,
+                HitRollModel::OneRoll,
+                &mut rng,
+            );
+            let other_outcome = CombatOutcome::resolve(
+                &forecast(50, 50),
+                20,
+                20,
+                UNIT_IDXS,
+                INFINITE_USES,
+                HitRollModel::OneRoll,
+                &mut other_rng,
+            );
+
+            assert_eq!(outcome, other_outcome);
+            assert_eq!(rng.get_state(), other_rng.get_state());
+        }
+
+        #[test]
+        fn weapons_out_of_uses_stop_striking() {
+            let mut rng = BattleRng::new(7);
+            let weapon_uses = [EntryUses::Finite(1), EntryUses::Infinite];
+            let outcome = CombatOutcome::resolve(
+                &forecast(100, 0),
+                20,
+                20,
+                UNIT_IDXS,
+                weapon_uses,
+                HitRollModel::OneRoll,
+                &mut rng,
+            );
+
+            assert_eq!(outcome.strikes_of(CombatSide::Attacker), 1);
+            assert_eq!(outcome.strikes_of(CombatSide::Defender), 1);
+            assert_eq!(outcome.defender_htp, 14);
+        }
+    }
+
+    mod hit_roll {
+        use super::*;
+        use crate::database::inventory::EntryUses;
+
+        #[test]
+        fn effective_hit_follows_the_model() {
+            assert_eq!(HitRollModel::OneRoll.effective_hit(70, 0), 70);
+            assert_eq!(HitRollModel::TwoRolls.effective_hit(70, 0), 82);
+            assert_eq!(HitRollModel::TwoRolls.effective_hit(30, 0), 18);
+            assert_eq!(HitRollModel::TwoRolls.effective_hit(100, 0), 100);
+            assert_eq!(HitRollModel::TwoRolls.effective_hit(0, 0), 0);
+            assert_eq!(HitRollModel::Fixed.effective_hit(50, FIXED_HIT_START), 100);
+            assert_eq!(HitRollModel::Fixed.effective_hit(49, FIXED_HIT_START), 0);
+            assert_eq!(HitRollModel::Fixed.effective_hit(30, 70), 100);
+        }
+
+        #[test]
+        fn fixed_model_lands_accumulated_chances() {
+            let mut rng = BattleRng::new(0);
+            let hit_roller = HitRoller::new(HitRollModel::Fixed, [0, 1]);
+
+            let landed = (0..4)
+                .map(|_| hit_roller.roll(CombatSide::Attacker, 30, &mut rng))
+                .collect::<Vec<_>>();
+
+            assert_eq!(landed, vec![false, true, false, false]);
+            // Fixed rolls leave the state of the generator untouched.
+            assert_eq!(rng.get_state(), 0);
+        }
+
+        #[test]
+        fn fixed_model_accumulates_across_combats() {
+            let mut rng = BattleRng::new(0);
+            let forecast = CombatForecast {
+                attacker: StrikeForecast {
+                    damage: 1,
+                    hit: 30,
+                    strikes: 1,
+                    ..Default::default()
+                },
+                order: vec![CombatSide::Attacker],
+                ..Default::default()
+            };
+
+            let landed = (0..4)
+                .map(|_| {
+                    let hit_accumulated = rng.hit_accumulated(0);
+                    let effective_hit = HitRollModel::Fixed.effective_hit(30, hit_accumulated);
+                    let outcome = CombatOutcome::resolve(
+                        &forecast,
+                        20,
+                        20,
+                        [0, 1],
+                        [EntryUses::Infinite; 2],
+                        HitRollModel::Fixed,
+                        &mut rng,
+                    );
+
+                    assert_eq!(outcome.strikes[0].hit, effective_hit == 100);
+                    outcome.strikes[0].hit
+                })
+                .collect::<Vec<_>>();
+
+            // One strike per combat, the 30 hit chance of each one carries over.
+            assert_eq!(landed, vec![false, true, false, false]);
+            assert_eq!(rng.hit_accumulated(0), 70);
+            // The defender never struck and keeps the starting chance.
+            assert_eq!(rng.hit_accumulated(1), FIXED_HIT_START);
+        }
+    }
+
+    mod flow {
+        use super::*;
+
+        use CombatSide::{Attacker, Defender};
+
+        #[test]
+        fn defender_with_attack_first_strikes_before() {
+            let vantage = CombatFlow {
+                attack_first: true,
+                ..Default::default()
+            };
+
+            let order = CombatFlow::strike_order(&CombatFlow::default(), &vantage, [1, 1]);
+            assert_eq!(order, vec![Defender, Attacker]);
+
+            // The attacker keeps the first strike if both attack first.
+            let order = CombatFlow::strike_order(&vantage, &vantage, [1, 1]);
+            assert_eq!(order, vec![Attacker, Defender]);
+        }
+
+        #[test]
+        fn no_second_wind_limits_the_opponent_strikes() {
+            let brave = CombatFlow {
+                brave_strike: true,
+                ..Default::default()
+            };
+            let no_second_wind = CombatFlow {
+                no_second_wind: true,
+                ..Default::default()
+            };
+
+            let order = CombatFlow::strike_order(&brave, &CombatFlow::default(), [2, 1]);
+            assert_eq!(
+                order,
+                vec![Attacker, Attacker, Defender, Attacker, Attacker]
+            );
+
+            let order = CombatFlow::strike_order(&brave, &no_second_wind, [2, 1]);
+            assert_eq!(order, vec![Attacker, Defender]);
+        }
+
+        #[test]
+        fn cancel_counter_overrides_attack_first() {
+            let cancel_counter = CombatFlow {
+                cancel_counter: true,
+                desperation: true,
+                ..Default::default()
+            };
+            let vantage = CombatFlow {
+                attack_first: true,
+                ..Default::default()
+            };
+
+            let order = CombatFlow::strike_order(&cancel_counter, &vantage, [2, 1]);
+            assert_eq!(order, vec![Attacker, Attacker]);
+
+            let desperation = CombatFlow {
+                desperation: true,
+                ..Default::default()
+            };
+
+            let order = CombatFlow::strike_order(&desperation, &vantage, [2, 1]);
+            assert_eq!(order, vec![Defender, Attacker, Attacker]);
+        }
+    }
+}
@@ -0,0 +1,152 @@
+use super::{CombatForecast, CombatSide, HitRoller, StrikeForecast};
+use crate::{
+    database::{difficulty::HitRollModel, inventory::EntryUses},
+    game_entities::{battle_rng::BattleRng, unit_data::UnitIdx},
+};
+
+use godot::prelude::*;
+
+/// Damage of a critical strike as a multiple of the damage of a regular one.
+const CRIT_DAMAGE_MULTIPLIER: i32 = 3;
+
+/// Outcome of a single strike of a combat.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) struct Strike {
+    pub(crate) side: CombatSide,
+    pub(crate) hit: bool,
+    pub(crate) crit: bool,
+    /// Damage dealt, 0 if the strike missed
+    pub(crate) damage: i32,
+    /// Hit points of the struck unit after the strike
+    pub(crate) target_htp: i32,
+}
+
+impl GodotConvert for Strike {
+    type Via = Dictionary;
+}
+
+impl ToGodot for Strike {
+    type ToVia<'v> = Dictionary;
+
+    fn to_godot(&self) -> Self::Via {
+        dict! {
+            "side": self.side as u8,
+            "hit": self.hit,
+            "crit": self.crit,
+            "damage": self.damage,
+            "target_htp": self.target_htp,
+        }
+    }
+}
+
+/// Strikes of a resolved combat, in order, and the resulting hit points of
+/// both sides.
+#[derive(Clone, Default, PartialEq, Eq, Debug)]
+pub(crate) struct CombatOutcome {
+    pub(crate) strikes: Vec<Strike>,
+    pub(crate) attacker_htp: i32,
+    pub(crate) defender_htp: i32,
+}
+
+impl CombatOutcome {
+    /// Resolves the combat of `forecast` between an attacker with
+    /// `attacker_htp` and a defender with `defender_htp`, rolling hits with
+    /// `hit_roll` and criticals with a single roll of `rng`, which also keeps
+    /// the hit chance accumulated by the units `unit_idxs`. Strikes follow
+    /// the order of the forecast until one of the sides is defeated, each
+    /// side stops striking once its `weapon_uses` run out.
+    pub(crate) fn resolve(
+        forecast: &CombatForecast,
+        attacker_htp: i32,
+        defender_htp: i32,
+        unit_idxs: [UnitIdx; 2],
+        weapon_uses: [EntryUses; 2],
+        hit_roll: HitRollModel,
+        rng: &mut BattleRng,
+    ) -> Self {
+        let hit_roller = HitRoller::new(hit_roll, unit_idxs);
+        let mut uses_left = weapon_uses;
+        let mut outcome = Self {
+            strikes: Vec::new(),
+            attacker_htp,
+            defender_htp,
+        };
+        for side in forecast.order.iter().copied() {
+            if outcome.attacker_htp <= 0 || outcome.defender_htp <= 0 {
+                break;
+            }
+
+            match &mut uses_left[side as usize] {
+                EntryUses::Finite(0) => continue,
+                EntryUses::Finite(uses) => *uses -= 1,
+                EntryUses::Infinite | EntryUses::NoUses => {}
+            }
+
+            let (striker, target_htp) = match side {
+                CombatSide::Attacker => (&forecast.attacker, &mut outcome.defender_htp),
+                CombatSide::Defender => (&forecast.defender, &mut outcome.attacker_htp),
+            };
+
+            let strike = Self::roll_strike(side, striker, target_htp, &hit_roller, rng);
+            outcome.strikes.push(strike);
+        }
+
+        outcome
+    }
+
+    /// Number of strikes of `side` in the combat.
+    pub(crate) fn strikes_of(&self, side: CombatSide) -> usize {
+        self.strikes
+            .iter()
+            .filter(|strike| strike.side == side)
+            .count()
+    }
+
+    fn roll_strike(
+        side: CombatSide,
+        striker: &StrikeForecast,
+        target_htp: &mut i32,
+        hit_roller: &HitRoller,
+        rng: &mut BattleRng,
+    ) -> Strike {
+        let hit = hit_roller.roll(side, striker.hit, rng);
+        let crit = hit && rng.roll_percent() < striker.crit;
+
+        let damage = match (hit, crit) {
+            (false, _) => 0,
+            (true, false) => striker.damage,
+            (true, true) => striker.damage * CRIT_DAMAGE_MULTIPLIER,
+        }
+        .min(*target_htp);
+
+        *target_htp -= damage;
+
+        Strike {
+            side,
+            hit,
+            crit,
+            damage,
+            target_htp: *target_htp,
+        }
+    }
+}
+
+impl GodotConvert for CombatOutcome {
+    type Via = Dictionary;
+}
+
+impl ToGodot for CombatOutcome {
+    type ToVia<'v> = Dictionary;
+
+    fn to_godot(&self) -> Self::Via {
+        dict! {
+            "strikes": self
+                .strikes
+                .iter()
+                .map(Strike::to_godot)
+                .collect::<Array<Dictionary>>(),
+            "attacker_htp": self.attacker_htp,
+            "defender_htp": self.defender_htp,
+        }
+    }
+}
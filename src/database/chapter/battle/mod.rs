@@ -11,6 +11,8 @@ use std::{collections::HashMap, fmt::Display};
 mod conditions;
 mod preparation;
 
+pub(crate) use conditions::{DefeatCondition, VictoryCondition};
+
 #[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
 pub(crate) struct Vector2u8 {
     pub(crate) x: u8,
@@ -99,11 +101,61 @@ impl FromGodot for Vector2u8 {
     }
 }
 
+/// Limits of the area guarded by a unit following `MovementBehaviour::Defend`,
+/// measured from its defend cell.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub(crate) struct DefendRadii {
+    /// How far the unit may stray from its defend cell, when 0 it never ends
+    /// its movement further away than it already is
+    #[serde(default)]
+    pub(crate) leash_radius: u8,
+    /// How close hostile units have to be to the defend cell to be engaged,
+    /// when not set every hostile unit in reach is engaged
+    #[serde(default)]
+    pub(crate) aggro_radius: Option<u8>,
+}
+
+impl GodotConvert for DefendRadii {
+    type Via = Dictionary;
+}
+
+impl ToGodot for DefendRadii {
+    type ToVia<'v> = Dictionary;
+
+    fn to_godot(&self) -> Self::Via {
+        dict! {
+            "leash_radius": self.leash_radius,
+            "aggro_radius": self.aggro_radius.map_or(-1, i32::from),
+        }
+    }
+}
+
+impl FromGodot for DefendRadii {
+    fn try_from_godot(via: Self::Via) -> Result<Self, ConvertError> {
+        Ok(Self::from_godot(via))
+    }
+
+    fn from_godot(via: Self::Via) -> Self {
+        Self {
+            leash_radius: via
+                .get("leash_radius")
+                .map_or(0, |radius| u8::from_variant(&radius)),
+            aggro_radius: via
+                .get("aggro_radius")
+                .map(|radius| i32::from_variant(&radius))
+                .and_then(|radius| u8::try_from(radius).ok()),
+        }
+    }
+}
+
 #[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub(crate) struct PlacementDetails {
     unit_id: UnitId,
     positions: Vec<Vector2u8>,
     personality: PersonalityId,
+    /// Only used if the personality has `MovementBehaviour::Defend` entries
+    #[serde(default)]
+    defend_radii: DefendRadii,
 }
 
 impl GodotConvert for PlacementDetails {
@@ -118,6 +170,7 @@ impl ToGodot for PlacementDetails {
             "unit_id": self.unit_id.clone(),
             "positions": self.positions.to_variant_array(),
             "personality": self.personality.clone(),
+            "defend_radii": self.defend_radii.to_godot(),
         }
     }
 }
@@ -147,8 +200,8 @@ pub(crate) struct BattleConfig {
     allied_armies: Vec<ArmyId>,
     active_armies: Vec<ArmyId>,
     starting_army: ArmyId,
-    victory_condition: conditions::VictoryCondition,
-    defeat_condition: conditions::DefeatCondition,
+    pub(crate) victory_condition: conditions::VictoryCondition,
+    pub(crate) defeat_condition: conditions::DefeatCondition,
     cursor_start: Vector2u8,
     unit_placements: ArmyPlacements,
 }
@@ -185,8 +238,8 @@ impl ToGodot for BattleConfig {
 
 #[cfg(feature = "verify_database")]
 mod verify {
-    use super::{BattleConfig, Vector2u8};
-    use crate::database::{DbConnector, chapter::ChapterKey};
+    use super::{BattleConfig, DefendRadii, Vector2u8};
+    use crate::database::{DbConnector, chapter::ChapterKey, personality::MovementBehaviour};
 
     use godot::{classes::ResourceLoader, global::godot_error};
     use std::collections::HashSet;
@@ -470,7 +523,7 @@ mod verify {
                             }
                         }
 
-                        if !db.personalities.contains_key(&placement.personality) {
+                        let Some(personality) = db.personalities.get(&placement.personality) else {
                             godot_error!(
                                 "[{}][{}] Unit personality identifier [{}] not found in database!",
                                 chapter_key,
@@ -478,6 +531,20 @@ mod verify {
                                 &placement.personality
                             );
                             return false;
+                        };
+
+                        let defends = std::iter::once(&personality.default)
+                            .chain(personality.conditional.values())
+                            .any(|behaviour| behaviour.movement == MovementBehaviour::Defend);
+
+                        if !defends && placement.defend_radii != DefendRadii::default() {
+                            godot_error!(
+                                "[{}][{}] Defend radii set for personality [{}] without Defend behaviour!",
+                                chapter_key,
+                                segment_idx,
+                                &placement.personality
+                            );
+                            return false;
                         }
                     }
                 } else {
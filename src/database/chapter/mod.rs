@@ -9,7 +9,7 @@ mod battle;
 mod camp;
 mod dialogue;
 
-pub(crate) use battle::Vector2u8;
+pub(crate) use battle::{DefeatCondition, DefendRadii, Vector2u8, VictoryCondition};
 pub(crate) use dialogue::{DialogueKey, DialogueSection};
 
 pub(crate) type ChapterKey = DbId;
@@ -51,7 +51,7 @@ pub(crate) struct ChapterEntry {
     code: GString,
     title: GString,
     background_id: GString,
-    segments: Vec<ChapterSegment>,
+    pub(crate) segments: Vec<ChapterSegment>,
     next_chapter: ChapterKey,
 }
 
@@ -0,0 +1,170 @@
+use super::{DbId, DbTable, IdColumn};
+
+use godot::prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub(crate) type CombatRulesId = DbId;
+
+/// Bonuses granted to a weapon with colour advantage over the weapon of its
+/// target, a weapon at a disadvantage gets them as penalties instead.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct TriangleBonus {
+    pub(crate) hit: i8,
+    pub(crate) damage: i8,
+}
+
+impl GodotConvert for TriangleBonus {
+    type Via = Dictionary;
+}
+
+impl ToGodot for TriangleBonus {
+    type ToVia<'v> = Dictionary;
+
+    fn to_godot(&self) -> Self::Via {
+        dict! {
+            "hit": self.hit,
+            "damage": self.damage,
+        }
+    }
+}
+
+/// Defense applied against `Piercing` weapons, taken from the lowest of the
+/// `def` and `spt` of the target. Terrain bonuses always apply in full.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all(deserialize = "snake_case"))]
+pub(crate) enum PiercingMitigation {
+    #[default]
+    Lowest = 0,
+    Halved = 1,
+    Ignored = 2,
+}
+
+/// Conditions for a unit to attack a second time after both sides attacked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct FollowUpRule {
+    /// Attack speed advantage over the opponent needed to attack again,
+    /// follow-ups are disabled if 0
+    pub(crate) speed_difference: u8,
+    /// Whether each point of strength below the `required_str` of the
+    /// weapon lowers the attack speed of the unit by one
+    pub(crate) weapon_weight: bool,
+}
+
+impl Default for FollowUpRule {
+    fn default() -> Self {
+        Self {
+            speed_difference: 4,
+            weapon_weight: true,
+        }
+    }
+}
+
+impl GodotConvert for FollowUpRule {
+    type Via = Dictionary;
+}
+
+impl ToGodot for FollowUpRule {
+    type ToVia<'v> = Dictionary;
+
+    fn to_godot(&self) -> Self::Via {
+        dict! {
+            "speed_difference": self.speed_difference,
+            "weapon_weight": self.weapon_weight,
+        }
+    }
+}
+
+/// Rules followed by the combat calculations.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct CombatRules {
+    /// Red weapons have advantage over green ones, green over blue and
+    /// blue over red
+    pub(crate) triangle: TriangleBonus,
+    pub(crate) piercing: PiercingMitigation,
+    pub(crate) follow_up: FollowUpRule,
+}
+
+impl GodotConvert for CombatRules {
+    type Via = Dictionary;
+}
+
+impl ToGodot for CombatRules {
+    type ToVia<'v> = Dictionary;
+
+    fn to_godot(&self) -> Self::Via {
+        dict! {
+            "triangle": self.triangle.to_godot(),
+            "piercing": self.piercing as u8,
+            "follow_up": self.follow_up.to_godot(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub(crate) struct CombatRulesEntry {
+    #[serde(flatten)]
+    _i: IdColumn,
+    #[serde(flatten)]
+    pub(crate) rules: CombatRules,
+}
+
+impl DbTable for CombatRulesEntry {
+    fn get_id(&self) -> DbId {
+        self._i._id.clone()
+    }
+}
+
+impl GodotConvert for CombatRulesEntry {
+    type Via = Dictionary;
+}
+
+impl ToGodot for CombatRulesEntry {
+    type ToVia<'v> = Dictionary;
+
+    fn to_godot(&self) -> Self::Via {
+        let mut entry_dict = self.rules.to_godot();
+        entry_dict.set("id", self._i._id.clone());
+
+        entry_dict
+    }
+}
+
+#[cfg(feature = "verify_database")]
+mod verify {
+    use super::CombatRulesEntry;
+    use crate::database::{DbConnector, validation::VerifyTable};
+
+    use godot::global::godot_error;
+
+    impl VerifyTable for CombatRulesEntry {
+        fn validate(&self, _: &DbConnector) -> bool {
+            if self._i._id.is_empty() {
+                godot_error!("[{}] Invalid combat rules row in database!", self._i._id);
+                return false;
+            }
+
+            let triangle = self.rules.triangle;
+
+            if triangle.hit < -100 || triangle.hit > 100 {
+                godot_error!(
+                    "[{}] Triangle 'hit' should be within the valid range (-100 to 100)!",
+                    self._i._id
+                );
+                return false;
+            }
+
+            if triangle.damage < -20 || triangle.damage > 20 {
+                godot_error!(
+                    "[{}] Triangle 'damage' should be within the valid range (-20 to 20)!",
+                    self._i._id
+                );
+                return false;
+            }
+
+            true
+        }
+    }
+}
@@ -0,0 +1,152 @@
+use super::{DbId, DbTable, IdColumn};
+
+use godot::prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub(crate) type DifficultyId = DbId;
+
+/// How random numbers decide whether a strike lands.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all(deserialize = "snake_case"))]
+pub(crate) enum HitRollModel {
+    /// Lands if a single roll is below the hit chance
+    #[default]
+    OneRoll = 0,
+    /// Lands if the average of two rolls is below the hit chance, making
+    /// high chances more and low chances less likely than displayed
+    TwoRolls = 1,
+    /// No rolls, the hit chance of every strike is accumulated and a strike
+    /// lands each time the total reaches 100
+    Fixed = 2,
+}
+
+impl GodotConvert for HitRollModel {
+    type Via = u8;
+}
+
+impl ToGodot for HitRollModel {
+    type ToVia<'v> = u8;
+
+    fn to_godot(&self) -> Self::ToVia<'_> {
+        *self as u8
+    }
+}
+
+impl FromGodot for HitRollModel {
+    fn try_from_godot(via: Self::Via) -> Result<Self, ConvertError> {
+        match via {
+            0 => Ok(Self::OneRoll),
+            1 => Ok(Self::TwoRolls),
+            2 => Ok(Self::Fixed),
+            other => Err(ConvertError::new(format!(
+                "Unknown HitRollModel value [{}]!",
+                other
+            ))),
+        }
+    }
+}
+
+/// Adjustments applied on top of the personalities of the units controlled by
+/// the AI, and to the combats of every unit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct DifficultyProfile {
+    /// Percentage applied to the `low_htp_percent` threshold of personalities,
+    /// lower values make wounded units keep fighting for longer.
+    pub(crate) caution_percent: u8,
+    /// Largest amount added at random to the score of each attack target,
+    /// higher values make the choice of target less predictable.
+    pub(crate) target_jitter: u8,
+    /// Units attack even if the counter attack is expected to defeat them.
+    pub(crate) allow_suicide: bool,
+    /// Units use the consumables in their inventory.
+    pub(crate) use_consumables: bool,
+    /// Model used to roll the strikes of every combat.
+    pub(crate) hit_roll: HitRollModel,
+}
+
+impl Default for DifficultyProfile {
+    fn default() -> Self {
+        Self {
+            caution_percent: 100,
+            target_jitter: 0,
+            allow_suicide: true,
+            use_consumables: true,
+            hit_roll: HitRollModel::default(),
+        }
+    }
+}
+
+impl GodotConvert for DifficultyProfile {
+    type Via = Dictionary;
+}
+
+impl ToGodot for DifficultyProfile {
+    type ToVia<'v> = Dictionary;
+
+    fn to_godot(&self) -> Self::Via {
+        dict! {
+            "caution_percent": self.caution_percent,
+            "target_jitter": self.target_jitter,
+            "allow_suicide": self.allow_suicide,
+            "use_consumables": self.use_consumables,
+            "hit_roll": self.hit_roll.to_godot(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub(crate) struct DifficultyEntry {
+    #[serde(flatten)]
+    _i: IdColumn,
+    #[serde(flatten)]
+    pub(crate) profile: DifficultyProfile,
+}
+
+impl DbTable for DifficultyEntry {
+    fn get_id(&self) -> DbId {
+        self._i._id.clone()
+    }
+}
+
+impl GodotConvert for DifficultyEntry {
+    type Via = Dictionary;
+}
+
+impl ToGodot for DifficultyEntry {
+    type ToVia<'v> = Dictionary;
+
+    fn to_godot(&self) -> Self::Via {
+        let mut entry_dict = self.profile.to_godot();
+        entry_dict.set("id", self._i._id.clone());
+
+        entry_dict
+    }
+}
+
+#[cfg(feature = "verify_database")]
+mod verify {
+    use super::DifficultyEntry;
+    use crate::database::{DbConnector, validation::VerifyTable};
+
+    use godot::global::godot_error;
+
+    impl VerifyTable for DifficultyEntry {
+        fn validate(&self, _: &DbConnector) -> bool {
+            if self._i._id.is_empty() {
+                godot_error!("[{}] Invalid difficulty row in database!", self._i._id);
+                return false;
+            }
+
+            if self.profile.caution_percent > 200 {
+                godot_error!(
+                    "[{}] Difficulty 'caution_percent' cannot be greater than 200!",
+                    self._i._id
+                );
+                return false;
+            }
+
+            true
+        }
+    }
+}
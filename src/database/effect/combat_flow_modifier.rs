@@ -4,8 +4,16 @@ use serde::{Deserialize, Serialize};
 #[derive(Copy, Clone, Serialize, Deserialize)]
 #[serde(rename_all(deserialize = "snake_case"))]
 pub enum CombatFlowEffect {
+    /// Strikes before the attacker when defending
     AttackFirst = 0,
+    /// The opponent strikes at most once
     NoSecondWind = 1,
+    /// Strikes twice on each attack when attacking
+    BraveStrike = 2,
+    /// Lands every attack before the counter of the defender when attacking
+    Desperation = 3,
+    /// The defender cannot strike back when attacking
+    CancelCounter = 4,
 }
 
 impl GodotConvert for CombatFlowEffect {
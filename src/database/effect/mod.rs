@@ -114,7 +114,7 @@ pub(crate) struct EffectEntry {
     #[serde(flatten)]
     _i: IdColumn,
     #[serde(flatten)]
-    variant: EffectVariant,
+    pub(crate) variant: EffectVariant,
 }
 
 impl DbTable for EffectEntry {
@@ -158,6 +158,70 @@ impl DbConnector {
         self.personalities.contains_key(&personality_id)
     }
 
+    #[func]
+    pub(crate) fn get_terrain(&self, terrain_id: DbId) -> Dictionary {
+        DbConnector::get_from(&self.terrain, &terrain_id)
+    }
+
+    #[func]
+    pub(crate) fn get_terrains(&self) -> Array<Dictionary> {
+        DbConnector::get_array_from(&self.terrain)
+    }
+
+    #[func]
+    pub(crate) fn has_terrain(&self, terrain_id: DbId) -> bool {
+        self.terrain.contains_key(&terrain_id)
+    }
+
+    #[func]
+    pub(crate) fn get_movement_class(&self, movement_class_id: DbId) -> Dictionary {
+        DbConnector::get_from(&self.movement_classes, &movement_class_id)
+    }
+
+    #[func]
+    pub(crate) fn has_movement_class(&self, movement_class_id: DbId) -> bool {
+        self.movement_classes.contains_key(&movement_class_id)
+    }
+
+    #[func]
+    pub(crate) fn get_difficulty(&self, difficulty_id: DbId) -> Dictionary {
+        DbConnector::get_from(&self.difficulties, &difficulty_id)
+    }
+
+    #[func]
+    pub(crate) fn get_difficulties(&self) -> Array<Dictionary> {
+        DbConnector::get_array_from(&self.difficulties)
+    }
+
+    #[func]
+    pub(crate) fn has_difficulty(&self, difficulty_id: DbId) -> bool {
+        self.difficulties.contains_key(&difficulty_id)
+    }
+
+    #[func]
+    pub(crate) fn get_combat_rules(&self, combat_rules_id: DbId) -> Dictionary {
+        DbConnector::get_from(&self.combat_rules, &combat_rules_id)
+    }
+
+    #[func]
+    pub(crate) fn get_all_combat_rules(&self) -> Array<Dictionary> {
+        DbConnector::get_array_from(&self.combat_rules)
+    }
+
+    #[func]
+    pub(crate) fn has_combat_rules(&self, combat_rules_id: DbId) -> bool {
+        self.combat_rules.contains_key(&combat_rules_id)
+    }
+
+    /// Tries to get the movement class identifier used by `role_id`.
+    /// Returns an empty identifier if no movement class could be resolved.
+    #[func]
+    pub(crate) fn get_movement_class_for_role(&self, role_id: DbId) -> DbId {
+        self.movement_class_for(&role_id)
+            .cloned()
+            .unwrap_or_default()
+    }
+
     #[func]
     fn verify_database(&self) -> bool {
         #[cfg(feature = "verify_database")]
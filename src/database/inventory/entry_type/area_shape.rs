@@ -0,0 +1,129 @@
+use godot::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Pattern of cells affected around the aimed cell of an effect, entries
+/// without an area keep affecting every target within their effect range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(
+    rename_all(deserialize = "snake_case"),
+    tag = "shape",
+    content = "size"
+)]
+pub(crate) enum AreaShape {
+    /// Cells within `size` steps of the aimed cell
+    Diamond(u8),
+    /// Cells within a square of radius `size` centered on the aimed cell
+    Square(u8),
+    /// The aimed cell and `size` cells in a straight line along every axis
+    /// of the grid, diagonals are not included
+    Cross(u8),
+    /// `size` cells in a straight line from the caster towards the aimed cell
+    Line(u8),
+    /// Cells within `size` steps of the caster widening towards the aimed cell
+    Cone(u8),
+    /// Same cells as `Diamond`, but only if the aimed cell holds a unit,
+    /// otherwise no cell is affected
+    Splash(u8),
+}
+
+impl AreaShape {
+    pub(crate) fn size(&self) -> u8 {
+        match self {
+            AreaShape::Diamond(size)
+            | AreaShape::Square(size)
+            | AreaShape::Cross(size)
+            | AreaShape::Line(size)
+            | AreaShape::Cone(size)
+            | AreaShape::Splash(size) => *size,
+        }
+    }
+
+    /// Returns **true** if the shape is oriented from the caster instead
+    /// of centered on the aimed cell.
+    pub(crate) fn is_directional(&self) -> bool {
+        matches!(self, AreaShape::Line(_) | AreaShape::Cone(_))
+    }
+}
+
+impl GodotConvert for AreaShape {
+    type Via = Dictionary;
+}
+
+impl ToGodot for AreaShape {
+    type ToVia<'v> = Dictionary;
+
+    fn to_godot(&self) -> Self::Via {
+        let shape = match self {
+            AreaShape::Diamond(_) => 0_u8,
+            AreaShape::Square(_) => 1,
+            AreaShape::Cross(_) => 2,
+            AreaShape::Line(_) => 3,
+            AreaShape::Cone(_) => 4,
+            AreaShape::Splash(_) => 5,
+        };
+
+        dict! {
+            "shape": shape,
+            "size": self.size(),
+        }
+    }
+}
+
+impl FromGodot for AreaShape {
+    fn try_from_godot(via: Self::Via) -> Result<Self, ConvertError> {
+        let (Some(shape), Some(size)) = (
+            via.get("shape").and_then(|shape| shape.try_to::<u8>().ok()),
+            via.get("size").and_then(|size| size.try_to::<u8>().ok()),
+        ) else {
+            return Err(ConvertError::new(format!(
+                "Invalid AreaShape dictionary {}!",
+                via
+            )));
+        };
+
+        match shape {
+            0 => Ok(AreaShape::Diamond(size)),
+            1 => Ok(AreaShape::Square(size)),
+            2 => Ok(AreaShape::Cross(size)),
+            3 => Ok(AreaShape::Line(size)),
+            4 => Ok(AreaShape::Cone(size)),
+            5 => Ok(AreaShape::Splash(size)),
+            other => Err(ConvertError::new(format!(
+                "Unknown AreaShape value [{}]!",
+                other
+            ))),
+        }
+    }
+}
+
+#[cfg(feature = "verify_database")]
+mod verify {
+    use super::AreaShape;
+
+    use godot::global::godot_error;
+
+    /// Largest size allowed for any area shape.
+    const MAX_AREA_SIZE: u8 = 5;
+
+    impl AreaShape {
+        pub(crate) fn validate(&self) -> bool {
+            let size = self.size();
+
+            if size == 0 && self.is_directional() {
+                godot_error!("Area shape [{:?}] cannot have zero size!", self);
+                return false;
+            }
+
+            if size > MAX_AREA_SIZE {
+                godot_error!(
+                    "Area shape [{:?}] size cannot be greater than {}!",
+                    self,
+                    MAX_AREA_SIZE
+                );
+                return false;
+            }
+
+            true
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use super::EffectTarget;
+use super::{AreaShape, EffectTarget};
 use crate::database::effect::EffectId;
 
 use godot::prelude::*;
@@ -9,6 +9,9 @@ pub(crate) struct ConsumableEntry {
     pub(crate) effect_id: EffectId,
     #[serde(flatten)]
     pub(crate) effect_target: EffectTarget,
+    /// Cells affected around the aimed cell
+    #[serde(default)]
+    pub(crate) area: Option<AreaShape>,
 }
 
 impl GodotConvert for ConsumableEntry {
@@ -21,6 +24,11 @@ impl ToGodot for ConsumableEntry {
     fn to_godot(&self) -> Self::Via {
         let mut target_dict = self.effect_target.to_godot();
         target_dict.set("effect_id", self.effect_id.clone());
+
+        if let Some(area) = self.area {
+            target_dict.set("area", area.to_godot());
+        }
+
         target_dict
     }
 }
@@ -83,6 +91,10 @@ mod verify {
                 return false;
             }
 
+            if self.area.is_some_and(|area| !area.validate()) {
+                return false;
+            }
+
             true
         }
     }
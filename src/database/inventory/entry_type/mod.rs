@@ -3,10 +3,12 @@ use crate::database::chapter::Vector2u8;
 use godot::prelude::*;
 use serde::{Deserialize, Serialize};
 
+mod area_shape;
 mod item_entry;
 mod support_entry;
 mod weapon_entry;
 
+pub(crate) use area_shape::*;
 pub(crate) use item_entry::*;
 pub(crate) use support_entry::*;
 pub(crate) use weapon_entry::*;
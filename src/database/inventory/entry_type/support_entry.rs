@@ -1,4 +1,4 @@
-use super::EffectTarget;
+use super::{AreaShape, EffectTarget};
 use crate::database::effect::EffectId;
 
 use godot::prelude::*;
@@ -31,6 +31,9 @@ pub(crate) struct SupportEntry {
     pub(crate) required_mag: u8,
     #[serde(default)]
     pub(crate) required_dex: u8,
+    /// Cells affected around the aimed cell
+    #[serde(default)]
+    pub(crate) area: Option<AreaShape>,
 }
 
 impl GodotConvert for SupportEntry {
@@ -54,6 +57,10 @@ impl ToGodot for SupportEntry {
 
         support_dict.extend_dictionary(&self.effect_target.to_godot(), true);
 
+        if let Some(area) = self.area {
+            support_dict.set("area", area.to_godot());
+        }
+
         support_dict
     }
 }
@@ -71,6 +78,10 @@ mod verify {
                 return false;
             }
 
+            if self.area.is_some_and(|area| !area.validate()) {
+                return false;
+            }
+
             if self.required_str == 0 && self.required_mag == 0 && self.required_dex == 0 {
                 godot_error!("At least one required attribute (str, mag, dex) must be non-zero!");
                 return false;
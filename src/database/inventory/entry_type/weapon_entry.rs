@@ -1,6 +1,7 @@
 use godot::prelude::*;
 use serde::{Deserialize, Serialize};
 
+use super::AreaShape;
 use crate::database::chapter::Vector2u8;
 
 #[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -19,7 +20,7 @@ pub(crate) enum WeaponCategory {
     Breath = 10,
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all(deserialize = "snake_case"))]
 pub(crate) enum WeaponColor {
     Red = 0,
@@ -27,7 +28,7 @@ pub(crate) enum WeaponColor {
     Green = 2,
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all(deserialize = "snake_case"))]
 pub(crate) enum WeaponDamageType {
     Physical = 0,
@@ -65,6 +66,9 @@ pub(crate) struct WeaponEntry {
     pub(crate) required_mag: u8,
     #[serde(default)]
     pub(crate) required_dex: u8,
+    /// Cells hit around the target, only the target is hit if not present
+    #[serde(default)]
+    pub(crate) area: Option<AreaShape>,
 }
 
 impl GodotConvert for WeaponEntry {
@@ -94,6 +98,10 @@ impl ToGodot for WeaponEntry {
         entry.set("required_mag", self.required_mag);
         entry.set("required_dex", self.required_dex);
 
+        if let Some(area) = self.area {
+            entry.set("area", area.to_godot());
+        }
+
         entry
     }
 }
@@ -152,6 +160,10 @@ mod verify {
                 return false;
             }
 
+            if self.area.is_some_and(|area| !area.validate()) {
+                return false;
+            }
+
             true
         }
     }
@@ -1,7 +1,7 @@
 use godot::prelude::*;
 use serde::{Deserialize, Deserializer, Serialize, de::Visitor};
 
-#[derive(Default, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub(crate) enum EntryUses {
     #[default]
     Infinite,
@@ -9,6 +9,21 @@ pub(crate) enum EntryUses {
     Finite(u8),
 }
 
+impl EntryUses {
+    /// Consumes `uses`, only `Finite` entries wear out.
+    ///
+    /// Returns **true** if the entry ran out of uses.
+    pub(crate) fn consume(&mut self, uses: u8) -> bool {
+        match self {
+            EntryUses::Finite(uses_left) => {
+                *uses_left = uses_left.saturating_sub(uses);
+                *uses_left == 0
+            }
+            EntryUses::Infinite | EntryUses::NoUses => false,
+        }
+    }
+}
+
 impl Serialize for EntryUses {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -9,12 +9,16 @@ use serde_json::from_slice as deserialize;
 
 pub(crate) mod army;
 pub(crate) mod chapter;
+pub(crate) mod combat_rules;
+pub(crate) mod difficulty;
 pub(crate) mod effect;
 pub(crate) mod inventory;
 pub(crate) mod kit;
+pub(crate) mod movement_class;
 pub(crate) mod personality;
 pub(crate) mod role;
 pub(crate) mod skill;
+pub(crate) mod terrain;
 pub(crate) mod unit;
 
 mod common;
@@ -36,6 +40,10 @@ pub(crate) struct DbConnector {
     pub(crate) skills: HashMap<DbId, skill::SkillEntry>,
     pub(crate) units: HashMap<DbId, unit::UnitEntry>,
     pub(crate) personalities: HashMap<DbId, personality::PersonalityEntry>,
+    pub(crate) terrain: HashMap<DbId, terrain::TerrainEntry>,
+    pub(crate) movement_classes: HashMap<DbId, movement_class::MovementClassEntry>,
+    pub(crate) difficulties: HashMap<DbId, difficulty::DifficultyEntry>,
+    pub(crate) combat_rules: HashMap<DbId, combat_rules::CombatRulesEntry>,
 }
 
 const TABLE_ARMIES: &str = "armies.json";
@@ -47,8 +55,32 @@ const TABLE_ROLES: &str = "roles.json";
 const TABLE_SKILLS: &str = "skills.json";
 const TABLE_UNITS: &str = "units.json";
 const TABLE_PERSONALITIES: &str = "personalities.json";
+const TABLE_TERRAIN: &str = "terrain.json";
+const TABLE_MOVEMENT_CLASSES: &str = "movement_classes.json";
+const TABLE_DIFFICULTIES: &str = "difficulties.json";
+const TABLE_COMBAT_RULES: &str = "combat_rules.json";
 
 impl DbConnector {
+    /// Resolves the movement class used by `role_id`, which is the class whose
+    /// role tags match exactly the role's tags. If there is no such class the
+    /// class without role tags is used instead.
+    pub(crate) fn movement_class_for(
+        &self,
+        role_id: &role::RoleId,
+    ) -> Option<&movement_class::MovementClassId> {
+        let role_entry = self.roles.get(role_id)?;
+
+        self.movement_classes
+            .iter()
+            .find(|(_, class)| class.role_tags == role_entry.role_tags)
+            .or_else(|| {
+                self.movement_classes
+                    .iter()
+                    .find(|(_, class)| class.role_tags.is_empty())
+            })
+            .map(|(class_id, _)| class_id)
+    }
+
     fn try_load_table<T>(at: &Path) -> Option<Vec<T>>
     where
         T: DeserializeOwned + DbTable,
@@ -122,6 +154,15 @@ impl DbConnector {
                 .extend(DbConnector::get_table_rows(&path.join(TABLE_UNITS)));
             self.personalities
                 .extend(DbConnector::get_table_rows(&path.join(TABLE_PERSONALITIES)));
+            self.terrain
+                .extend(DbConnector::get_table_rows(&path.join(TABLE_TERRAIN)));
+            self.movement_classes.extend(DbConnector::get_table_rows(
+                &path.join(TABLE_MOVEMENT_CLASSES),
+            ));
+            self.difficulties
+                .extend(DbConnector::get_table_rows(&path.join(TABLE_DIFFICULTIES)));
+            self.combat_rules
+                .extend(DbConnector::get_table_rows(&path.join(TABLE_COMBAT_RULES)));
 
             godot_print!("[RustExtensions]: Finished loading database!");
         } else {
@@ -0,0 +1,121 @@
+use super::{DbId, DbTable, IdColumn, role::RoleTags, terrain::TerrainId};
+use crate::traits::ToVariantArray;
+
+use godot::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_with::rust::maps_duplicate_key_is_error;
+use std::collections::{HashMap, HashSet};
+
+pub(crate) type MovementClassId = DbId;
+
+#[derive(Serialize, Deserialize)]
+pub(crate) struct MovementClassEntry {
+    #[serde(flatten)]
+    pub(crate) _i: IdColumn,
+    /// Roles whose `role_tags` match exactly this set use the movement class,
+    /// the entry with no tags is used for roles without a matching entry.
+    #[serde(default)]
+    pub(crate) role_tags: HashSet<RoleTags>,
+    /// Overrides of the terrain's default `move_cost`
+    #[serde(default, with = "maps_duplicate_key_is_error")]
+    pub(crate) costs: HashMap<TerrainId, u8>,
+    #[serde(default)]
+    pub(crate) impassable: HashSet<TerrainId>,
+}
+
+impl DbTable for MovementClassEntry {
+    fn get_id(&self) -> DbId {
+        self._i._id.clone()
+    }
+}
+
+impl GodotConvert for MovementClassEntry {
+    type Via = Dictionary;
+}
+
+impl ToGodot for MovementClassEntry {
+    type ToVia<'v> = Dictionary;
+
+    fn to_godot(&self) -> Self::Via {
+        dict! {
+            "id": self._i._id.clone(),
+            "role_tags": self.role_tags.to_variant_array(),
+            "costs": self.costs
+                .iter()
+                .map(|(terrain_id, cost)| (terrain_id.clone(), *cost))
+                .collect::<Dictionary>(),
+            "impassable": self.impassable.to_variant_array(),
+        }
+    }
+}
+
+#[cfg(feature = "verify_database")]
+mod verify {
+    use super::MovementClassEntry;
+    use crate::database::{DbConnector, validation::VerifyTable};
+
+    use godot::global::godot_error;
+
+    impl VerifyTable for MovementClassEntry {
+        fn validate(&self, db: &DbConnector) -> bool {
+            if self._i._id.is_empty() {
+                godot_error!("[{}] Invalid movement class row in database!", self._i._id);
+                return false;
+            }
+
+            for (terrain_id, cost) in self.costs.iter() {
+                if !db.terrain.contains_key(terrain_id) {
+                    godot_error!(
+                        "[{}] Movement class cost terrain [{}] not found in database!",
+                        self._i._id,
+                        terrain_id
+                    );
+                    return false;
+                }
+
+                if *cost == 0 {
+                    godot_error!(
+                        "[{}] Movement class cost for [{}] cannot be zero, use 'impassable' instead!",
+                        self._i._id,
+                        terrain_id
+                    );
+                    return false;
+                }
+
+                if self.impassable.contains(terrain_id) {
+                    godot_error!(
+                        "[{}] Terrain [{}] is both in 'costs' and 'impassable'!",
+                        self._i._id,
+                        terrain_id
+                    );
+                    return false;
+                }
+            }
+
+            for terrain_id in self.impassable.iter() {
+                if !db.terrain.contains_key(terrain_id) {
+                    godot_error!(
+                        "[{}] Movement class impassable terrain [{}] not found in database!",
+                        self._i._id,
+                        terrain_id
+                    );
+                    return false;
+                }
+            }
+
+            if db
+                .movement_classes
+                .values()
+                .any(|other| other._i._id != self._i._id && other.role_tags == self.role_tags)
+            {
+                godot_error!(
+                    "[{}] Another movement class uses the same role tags!",
+                    self._i._id
+                );
+                return false;
+            }
+
+            true
+        }
+    }
+}
@@ -7,7 +7,7 @@ use std::collections::HashMap;
 
 pub(crate) type PersonalityId = DbId;
 
-#[derive(Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all(deserialize = "snake_case"))]
 pub(crate) enum BehaviourKey {
     /// The unit's key behaviour when it has low health.
@@ -22,7 +22,7 @@ pub(crate) enum BehaviourKey {
     AlliesClose = 3,
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all(deserialize = "snake_case"))]
 pub(crate) enum MovementBehaviour {
     /// The unit will not move.
@@ -34,11 +34,12 @@ pub(crate) enum MovementBehaviour {
     Vanguard = 2,
     /// The unit will move away from the enemies.
     Evade = 3,
-    /// The unit will move according to the objective.
+    /// The unit will move according to the objective of the battle, avoiding
+    /// the cells hostile units can attack.
     Tactician = 4,
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all(deserialize = "snake_case"))]
 pub(crate) enum ActionBehaviour {
     /// The unit will do nothing.
@@ -61,7 +62,7 @@ pub(crate) enum ActionBehaviour {
     DebuffEnemies = 8,
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub(crate) struct UnitBehaviour {
     /// The priority of the behaviour. `UnitBehaviour` entries with **higher
     /// priority will take precedence** over those with lower priority.
@@ -71,6 +72,46 @@ pub(crate) struct UnitBehaviour {
     pub(crate) action: ActionBehaviour,
 }
 
+/// Parameters that decide when each `BehaviourKey` of a personality is active.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct BehaviourThresholds {
+    /// `LowHtp` is active when the unit's htp is at or below this
+    /// percentage of its maximum htp.
+    pub(crate) low_htp_percent: u8,
+    /// `LowValor` is active when the unit's valor is at or below this value.
+    pub(crate) low_valor: u8,
+    /// Units within this distance are considered close to the unit, if zero
+    /// the unit's current movement plus one is used.
+    pub(crate) close_radius: u8,
+}
+
+impl Default for BehaviourThresholds {
+    fn default() -> Self {
+        Self {
+            low_htp_percent: 30,
+            low_valor: 0,
+            close_radius: 0,
+        }
+    }
+}
+
+impl GodotConvert for BehaviourThresholds {
+    type Via = Dictionary;
+}
+
+impl ToGodot for BehaviourThresholds {
+    type ToVia<'v> = Dictionary;
+
+    fn to_godot(&self) -> Self::ToVia<'_> {
+        dict! {
+            "low_htp_percent": self.low_htp_percent,
+            "low_valor": self.low_valor,
+            "close_radius": self.close_radius,
+        }
+    }
+}
+
 impl GodotConvert for UnitBehaviour {
     type Via = Dictionary;
 }
@@ -80,6 +121,7 @@ impl ToGodot for UnitBehaviour {
 
     fn to_godot(&self) -> Self::ToVia<'_> {
         dict! {
+            "priority": self.priority,
             "movement": self.movement as u8,
             "action": self.action as u8,
         }
@@ -90,9 +132,11 @@ impl ToGodot for UnitBehaviour {
 pub(crate) struct PersonalityEntry {
     #[serde(flatten)]
     _i: IdColumn,
-    default: UnitBehaviour,
+    pub(crate) default: UnitBehaviour,
     #[serde(default, with = "maps_duplicate_key_is_error")]
-    conditional: HashMap<BehaviourKey, UnitBehaviour>,
+    pub(crate) conditional: HashMap<BehaviourKey, UnitBehaviour>,
+    #[serde(default)]
+    pub(crate) thresholds: BehaviourThresholds,
 }
 
 impl DbTable for PersonalityEntry {
@@ -119,6 +163,7 @@ impl ToGodot for PersonalityEntry {
             "id": self._i._id.clone(),
             "default": self.default.to_godot(),
             "conditional": conditional_dict,
+            "thresholds": self.thresholds.to_godot(),
         }
     }
 }
@@ -153,6 +198,14 @@ mod verify {
                 visited_priorities.insert(behaviour.priority);
             }
 
+            if self.thresholds.low_htp_percent > 100 {
+                godot_error!(
+                    "[{}] Personality 'low_htp_percent' cannot be greater than 100!",
+                    self._i._id
+                );
+                return false;
+            }
+
             true
         }
     }
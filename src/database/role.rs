@@ -18,7 +18,6 @@ pub(crate) enum ValorType {
 
 #[derive(Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all(deserialize = "snake_case"))]
-#[serde(untagged)]
 pub(crate) enum RoleTags {
     Cavalry = 0,
     Flying = 1,
@@ -60,7 +59,7 @@ pub(crate) struct RoleEntry {
     #[serde(default)]
     valor_type: ValorType,
     #[serde(default)]
-    role_tags: HashSet<RoleTags>,
+    pub(crate) role_tags: HashSet<RoleTags>,
 }
 
 impl DbTable for RoleEntry {
@@ -41,10 +41,10 @@ pub(crate) struct SkillEntry {
     _i: IdColumn,
     #[serde(flatten)]
     _n: NameDescColumns,
-    effect_id: EffectId,
-    trigger: SkillTrigger,
+    pub(crate) effect_id: EffectId,
+    pub(crate) trigger: SkillTrigger,
     #[serde(default)]
-    trigger_condition: SkillTriggerCondition,
+    pub(crate) trigger_condition: SkillTriggerCondition,
     #[serde(default)]
     slot_type: SlotType,
     #[serde(default)]
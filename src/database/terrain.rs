@@ -0,0 +1,93 @@
+use super::{DbId, DbTable, IdColumn, NameDescColumns};
+
+use godot::prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub(crate) type TerrainId = DbId;
+
+const fn default_move_cost() -> u8 {
+    1
+}
+
+#[derive(Serialize, Deserialize)]
+pub(crate) struct TerrainEntry {
+    #[serde(flatten)]
+    pub(crate) _i: IdColumn,
+    #[serde(flatten)]
+    pub(crate) _n: NameDescColumns,
+    /// Cost of entering the terrain for movement classes
+    /// that don't override it.
+    #[serde(default = "default_move_cost")]
+    pub(crate) move_cost: u8,
+    #[serde(default)]
+    pub(crate) avo_bonus: i8,
+    #[serde(default)]
+    pub(crate) def_bonus: i8,
+}
+
+impl DbTable for TerrainEntry {
+    fn get_id(&self) -> DbId {
+        self._i._id.clone()
+    }
+}
+
+impl GodotConvert for TerrainEntry {
+    type Via = Dictionary;
+}
+
+impl ToGodot for TerrainEntry {
+    type ToVia<'v> = Dictionary;
+
+    fn to_godot(&self) -> Self::Via {
+        dict! {
+            "id": self._i._id.clone(),
+            "name": self._n.name.clone(),
+            "description": self._n.description.clone(),
+            "move_cost": self.move_cost,
+            "avo_bonus": self.avo_bonus,
+            "def_bonus": self.def_bonus,
+        }
+    }
+}
+
+#[cfg(feature = "verify_database")]
+mod verify {
+    use super::TerrainEntry;
+    use crate::database::{DbConnector, validation::VerifyTable};
+
+    use godot::global::godot_error;
+
+    impl VerifyTable for TerrainEntry {
+        fn validate(&self, _db: &DbConnector) -> bool {
+            if self._i._id.is_empty() || self._n.is_empty() {
+                godot_error!("[{}] Invalid terrain row in database!", self._i._id);
+                return false;
+            }
+
+            if self.move_cost == 0 {
+                godot_error!("[{}] Terrain 'move_cost' cannot be zero!", self._i._id);
+                return false;
+            }
+
+            if self.avo_bonus < -50 || self.avo_bonus > 50 {
+                godot_error!(
+                    "[{}] avo_bonus out of range: {}",
+                    self._i._id,
+                    self.avo_bonus
+                );
+                return false;
+            }
+
+            if self.def_bonus < -10 || self.def_bonus > 10 {
+                godot_error!(
+                    "[{}] def_bonus out of range: {}",
+                    self._i._id,
+                    self.def_bonus
+                );
+                return false;
+            }
+
+            true
+        }
+    }
+}
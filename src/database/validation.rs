@@ -22,6 +22,10 @@ impl DbConnector {
             && DbConnector::ensure_all_ids_unique_for("skills", &self.skills)
             && DbConnector::ensure_all_ids_unique_for("units", &self.units)
             && DbConnector::ensure_all_ids_unique_for("personalities", &self.personalities)
+            && DbConnector::ensure_all_ids_unique_for("terrain", &self.terrain)
+            && DbConnector::ensure_all_ids_unique_for("movement_classes", &self.movement_classes)
+            && DbConnector::ensure_all_ids_unique_for("difficulties", &self.difficulties)
+            && DbConnector::ensure_all_ids_unique_for("combat_rules", &self.combat_rules)
     }
 
     fn ensure_all_ids_unique_for<T>(table_name: &str, table: &HashMap<DbId, T>) -> bool {
@@ -47,6 +51,10 @@ impl DbConnector {
             && DbConnector::ensure_all_rows_valid_for(&self.skills, self)
             && DbConnector::ensure_all_rows_valid_for(&self.units, self)
             && DbConnector::ensure_all_rows_valid_for(&self.personalities, self)
+            && DbConnector::ensure_all_rows_valid_for(&self.terrain, self)
+            && DbConnector::ensure_all_rows_valid_for(&self.movement_classes, self)
+            && DbConnector::ensure_all_rows_valid_for(&self.difficulties, self)
+            && DbConnector::ensure_all_rows_valid_for(&self.combat_rules, self)
     }
 
     fn ensure_all_rows_valid_for<T>(table: &HashMap<DbId, T>, connector: &Self) -> bool
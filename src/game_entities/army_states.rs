@@ -71,6 +71,34 @@ impl ArmyStates {
 
         is_valid
     }
+
+    /// Returns **true** if units of `army_id` and `other_army_id` fight each other.
+    pub(crate) fn are_hostile(&self, army_id: &ArmyId, other_army_id: &ArmyId) -> bool {
+        if army_id == other_army_id {
+            false
+        } else if army_id == &self.player_army || self.allied_armies.contains(army_id) {
+            self.enemy_armies.contains(other_army_id)
+        } else if self.enemy_armies.contains(army_id) {
+            self.allied_armies.contains(other_army_id) || other_army_id == &self.player_army
+        } else {
+            false
+        }
+    }
+
+    /// Returns **true** if units of `army_id` and `other_army_id` fight on the same side.
+    pub(crate) fn are_friendly(&self, army_id: &ArmyId, other_army_id: &ArmyId) -> bool {
+        if army_id == other_army_id {
+            true
+        } else if army_id == &self.player_army {
+            self.allied_armies.contains(other_army_id)
+        } else if self.allied_armies.contains(army_id) {
+            self.allied_armies.contains(other_army_id) || other_army_id == &self.player_army
+        } else if self.enemy_armies.contains(army_id) {
+            self.enemy_armies.contains(other_army_id)
+        } else {
+            false
+        }
+    }
 }
 
 const EXPECTED_DB_PARAMS_KEYS: &[&str] = &[
@@ -250,7 +278,7 @@ impl ArmyStates {
     /// Tries to get the currently active army_id.
     /// Returns an empty id if not found
     #[func]
-    fn try_get_active_army_id(&self) -> ArmyId {
+    pub(crate) fn try_get_active_army_id(&self) -> ArmyId {
         if let Some(army_id) = self.participant_armies.get(self.current_phase_idx) {
             army_id.clone()
         } else {
@@ -0,0 +1,157 @@
+use crate::{combat::FIXED_HIT_START, game_entities::unit_data::UnitIdx, traits::GetAs};
+
+use godot::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Table of unit idx to the hit chance it accumulated under
+/// `HitRollModel::Fixed`, carried from one combat to the next.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub(crate) struct HitAccumulators(HashMap<UnitIdx, i32>);
+
+impl GodotConvert for HitAccumulators {
+    type Via = Dictionary;
+}
+
+impl ToGodot for HitAccumulators {
+    type ToVia<'v> = Dictionary;
+
+    fn to_godot(&self) -> Self::Via {
+        self.0
+            .iter()
+            .map(|(idx, accumulated)| (idx.to_variant(), accumulated.to_variant()))
+            .collect()
+    }
+}
+
+impl FromGodot for HitAccumulators {
+    fn try_from_godot(via: Self::Via) -> Result<Self, ConvertError> {
+        via.iter_shared()
+            .map(|(idx, accumulated)| {
+                Ok((
+                    UnitIdx::try_from_variant(&idx)?,
+                    i32::try_from_variant(&accumulated)?,
+                ))
+            })
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+}
+
+/// Random number generator of a battle. Its state is stored in the
+/// `rand_state` of save points, so reloading a save reproduces the same
+/// outcomes.
+///
+/// The generator advances with every combat resolved with it, so its state
+/// has to be saved after each combat: pass `get_save_data` to
+/// `SaveManager.overwrite` at the `Battle` point, and restore it with
+/// `from_save_point` when the battle is loaded. The saved data also holds
+/// the `hit_accumulated` of every unit, which persists the strikes rolled
+/// under `HitRollModel::Fixed` across combats.
+#[derive(GodotClass, Default, Clone)]
+#[class(no_init, base=RefCounted)]
+pub(crate) struct BattleRng {
+    state: u64,
+    hit_accumulated: HitAccumulators,
+}
+
+#[godot_api]
+impl BattleRng {
+    /// Creates a generator resuming from `rand_state`, as returned by
+    /// `get_state`. Any value can be used to seed a new battle.
+    #[func]
+    fn from_state(rand_state: i64) -> Gd<Self> {
+        Gd::from_object(Self::new(rand_state))
+    }
+
+    /// Returns the state to store in the `rand_state` of a save point.
+    #[func]
+    pub(crate) fn get_state(&self) -> i64 {
+        self.state as i64
+    }
+
+    /// Creates a generator resuming from the `rand_state` and
+    /// `hit_accumulated` of `save_point`, as returned by
+    /// `SaveManager.get_auto` or `SaveManager.get_idx`.
+    #[func]
+    fn from_save_point(save_point: Dictionary) -> Gd<Self> {
+        let mut battle_rng = Self::new(save_point.get_as("rand_state", 0));
+        battle_rng.hit_accumulated =
+            save_point.get_as("hit_accumulated", HitAccumulators::default());
+
+        Gd::from_object(battle_rng)
+    }
+
+    /// Returns the state of the generator in the format of save points, to
+    /// be merged into one with `SaveManager.overwrite`.
+    #[func]
+    fn get_save_data(&self) -> Dictionary {
+        dict! {
+            "rand_state": self.get_state(),
+            "hit_accumulated": self.hit_accumulated.to_godot(),
+        }
+    }
+
+    #[func]
+    fn duplicate(&self) -> Gd<Self> {
+        Gd::from_object(self.clone())
+    }
+
+    /// Rolls a number from 0 to 99.
+    #[func]
+    pub(crate) fn roll_percent(&mut self) -> i32 {
+        (self.next_u64() % 100) as i32
+    }
+}
+
+impl BattleRng {
+    pub(crate) fn new(rand_state: i64) -> Self {
+        Self {
+            state: rand_state as u64,
+            hit_accumulated: HitAccumulators::default(),
+        }
+    }
+
+    /// Hit chance accumulated by the unit `unit_idx` under
+    /// `HitRollModel::Fixed`, units that never struck start at
+    /// `FIXED_HIT_START`.
+    pub(crate) fn hit_accumulated(&self, unit_idx: UnitIdx) -> i32 {
+        self.hit_accumulated
+            .0
+            .get(&unit_idx)
+            .copied()
+            .unwrap_or(FIXED_HIT_START)
+    }
+
+    /// Adds the displayed `hit` chance of a strike of the unit `unit_idx` to
+    /// its accumulated chance under `HitRollModel::Fixed`.
+    ///
+    /// Returns **true** if the strike lands, once the total reaches 100.
+    pub(crate) fn accumulate_hit(&mut self, unit_idx: UnitIdx, hit: i32) -> bool {
+        let accumulated = self
+            .hit_accumulated
+            .0
+            .entry(unit_idx)
+            .or_insert(FIXED_HIT_START);
+        *accumulated += hit.clamp(0, 100);
+
+        if *accumulated >= 100 {
+            *accumulated -= 100;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// SplitMix64, small and fast with a state that fits in an `i64`.
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+
+        let mut value = self.state;
+        value = (value ^ (value >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        value = (value ^ (value >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+
+        value ^ (value >> 31)
+    }
+}
@@ -1,4 +1,6 @@
 pub(crate) mod army_states;
+pub(crate) mod battle_rng;
 pub(crate) mod index_store;
+pub(crate) mod range_cache;
 pub(crate) mod unit_data;
 pub(crate) mod unit_states;
@@ -0,0 +1,70 @@
+use super::unit_data::UnitIdx;
+use crate::{database::movement_class::MovementClassId, pathfinding::TerrainGrid};
+
+use godot::prelude::*;
+use std::collections::{HashMap, HashSet};
+
+/// Inputs of a range computation that are not tracked through cell changes,
+/// cached ranges are only valid while all of them stay the same.
+#[derive(Clone, PartialEq)]
+pub(crate) struct RangeKey {
+    pub(crate) grid_id: InstanceId,
+    pub(crate) origin: Vector2i,
+    pub(crate) move_range: i32,
+    pub(crate) movement_class: MovementClassId,
+    pub(crate) attack_range: Vector2i,
+    pub(crate) support_range: Vector2i,
+    pub(crate) zone_of_control: bool,
+}
+
+/// Movement and interaction ranges of a unit for a given state of the map.
+#[derive(Clone)]
+pub(crate) struct CachedRanges {
+    pub(crate) key: RangeKey,
+    /// Revision of the terrain grid the ranges were computed with
+    pub(crate) grid_revision: u64,
+    pub(crate) move_costs: HashMap<Vector2i, i32>,
+    pub(crate) attack_cells: HashSet<Vector2i>,
+    pub(crate) support_cells: HashSet<Vector2i>,
+    /// Cells whose occupancy or terrain can change the movement range
+    pub(crate) reach: HashSet<Vector2i>,
+}
+
+#[derive(Default, Clone)]
+pub(crate) struct RangeCache {
+    entries: HashMap<UnitIdx, CachedRanges>,
+}
+
+impl RangeCache {
+    /// Returns the cached ranges of `unit_idx` if they were computed with the
+    /// same `key` and no cell within their reach changed in `terrain_grid`.
+    pub(crate) fn get(
+        &self,
+        unit_idx: UnitIdx,
+        key: &RangeKey,
+        terrain_grid: &TerrainGrid,
+    ) -> Option<&CachedRanges> {
+        self.entries.get(&unit_idx).filter(|ranges| {
+            ranges.key == *key && !terrain_grid.changed_since(ranges.grid_revision, &ranges.reach)
+        })
+    }
+
+    pub(crate) fn insert(&mut self, unit_idx: UnitIdx, ranges: CachedRanges) {
+        self.entries.insert(unit_idx, ranges);
+    }
+
+    pub(crate) fn remove(&mut self, unit_idx: UnitIdx) {
+        self.entries.remove(&unit_idx);
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Drops the cached ranges whose reach contains any of `cells`, used when
+    /// the occupancy of those cells changes.
+    pub(crate) fn invalidate_cells(&mut self, cells: &[Vector2i]) {
+        self.entries
+            .retain(|_, ranges| !cells.iter().any(|cell| ranges.reach.contains(cell)));
+    }
+}
@@ -1,7 +1,8 @@
 use super::*;
 
 use crate::{
-    game_entities::index_store::IndexStore, traits::ToVariantArray, traits::ToVariantOption,
+    combat::CombatStats, game_entities::index_store::IndexStore, traits::ToVariantArray,
+    traits::ToVariantOption,
 };
 
 #[godot_api]
@@ -107,42 +108,42 @@ impl UnitData {
     }
 
     #[func]
-    fn get_current_max_htp(&self) -> u8 {
+    pub(crate) fn get_current_max_htp(&self) -> u8 {
         self.base_htp.saturating_add_signed(self.mod_htp)
     }
 
     #[func]
-    fn get_current_str(&self) -> u8 {
+    pub(crate) fn get_current_str(&self) -> u8 {
         self.base_str.saturating_add_signed(self.mod_str)
     }
 
     #[func]
-    fn get_current_mag(&self) -> u8 {
+    pub(crate) fn get_current_mag(&self) -> u8 {
         self.base_mag.saturating_add_signed(self.mod_mag)
     }
 
     #[func]
-    fn get_current_def(&self) -> u8 {
+    pub(crate) fn get_current_def(&self) -> u8 {
         self.base_def.saturating_add_signed(self.mod_def)
     }
 
     #[func]
-    fn get_current_spt(&self) -> u8 {
+    pub(crate) fn get_current_spt(&self) -> u8 {
         self.base_spt.saturating_add_signed(self.mod_spt)
     }
 
     #[func]
-    fn get_current_agi(&self) -> u8 {
+    pub(crate) fn get_current_agi(&self) -> u8 {
         self.base_agi.saturating_add_signed(self.mod_agi)
     }
 
     #[func]
-    fn get_current_dex(&self) -> u8 {
+    pub(crate) fn get_current_dex(&self) -> u8 {
         self.base_dex.saturating_add_signed(self.mod_dex)
     }
 
     #[func]
-    fn get_current_mov(&self) -> u8 {
+    pub(crate) fn get_current_mov(&self) -> u8 {
         self.base_mov.saturating_add_signed(self.mod_mov)
     }
 
@@ -185,36 +186,29 @@ impl UnitData {
     /// Formula : `(current_dex * 5) + (current_ag * 2)`
     #[func]
     fn get_base_hit(&self) -> u8 {
-        self.get_current_dex()
-            .saturating_mul(5)
-            .saturating_add(self.get_current_agi().saturating_mul(2))
+        Self::saturate_stat(CombatStats::of(self).base_hit())
     }
 
     /// Formula : `(current_agi * 5) + current_dex`
     #[func]
     fn get_base_avoid(&self) -> u8 {
-        self.get_current_agi()
-            .saturating_mul(5)
-            .saturating_add(self.get_current_dex())
+        Self::saturate_stat(CombatStats::of(self).base_avoid())
     }
 
     /// Formula : `(current_dex * 2) + current_agi`
     #[func]
     fn get_base_crit(&self) -> u8 {
-        self.get_current_dex()
-            .saturating_mul(2)
-            .saturating_add(self.get_current_agi())
+        Self::saturate_stat(CombatStats::of(self).base_crit())
     }
 
     /// Formula : `current_dex + (current_agi * 2)`
     #[func]
     fn get_base_dodge(&self) -> u8 {
-        self.get_current_dex()
-            .saturating_add(self.get_current_agi().saturating_mul(2))
+        Self::saturate_stat(CombatStats::of(self).base_dodge())
     }
 
     #[func]
-    fn can_attack(&self) -> bool {
+    pub(crate) fn can_attack(&self) -> bool {
         self.attack_range > Vector2i::ZERO
     }
 
@@ -341,6 +335,18 @@ impl UnitData {
             .unwrap_or(-1)
     }
 
+    /// Consumes a use of the entry in the slot at `slot_idx`, after using a
+    /// staff or a consumable. Entries running out of uses are removed from the
+    /// inventory and their item index is returned. Otherwise returns -1.
+    #[func]
+    fn consume_slot_use(&mut self, slot_idx: i32, db: Gd<DbConnector>) -> i32 {
+        usize::try_from(slot_idx)
+            .ok()
+            .and_then(|slot_idx| self.consume_slot_uses(slot_idx, 1, db.bind()))
+            .map(|item_idx| item_idx as i32)
+            .unwrap_or(-1)
+    }
+
     /// Returns **true** if the unit can participate in interactions
     /// no matter what type.
     #[func]
@@ -478,6 +484,13 @@ impl UnitData {
         }
     }
 
+    /// Resolves again the unit's movement class, must be called
+    /// after changing the unit's `active_role_id`.
+    #[func]
+    fn refresh_movement_class(&mut self, db: Gd<DbConnector>) {
+        self.recompute_movement_class(&db.bind());
+    }
+
     #[func]
     fn duplicate(&self) -> Gd<Self> {
         Gd::from_object(self.clone())
@@ -49,6 +49,7 @@ impl UnitData {
 
         unit_data.active_role_id = unit_entry.role_id.clone();
         unit_data.active_kit_id = unit_entry.kit_id.clone();
+        unit_data.recompute_movement_class(&db_link);
 
         unit_data.personal_skill_id = unit_entry.personal_skill_id.clone();
         unit_data.equipped_skill_ids = unit_entry.equipped_skill_ids.clone();
@@ -156,6 +157,7 @@ impl UnitData {
 
         unit_data.active_role_id = RoleId::from_variant(&combined_data.at("active_role"));
         unit_data.active_kit_id = KitId::from_variant(&combined_data.at("active_kit"));
+        unit_data.recompute_movement_class(&db_link);
 
         unit_data.personal_skill_id = {
             let base_string = SkillId::from_variant(&combined_data.at("personal_skill"));
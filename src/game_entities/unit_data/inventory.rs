@@ -4,9 +4,25 @@ use super::*;
 
 #[derive(Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub(crate) struct SlotEntry {
-    pub(super) id: InventoryId,
+    pub(crate) id: InventoryId,
     pub(super) idx: InventoryIdx,
-    pub(super) uses: EntryUses,
+    pub(crate) uses: EntryUses,
+}
+
+#[cfg(test)]
+impl SlotEntry {
+    /// Builds an entry with an empty id, as ids cannot be built outside of
+    /// the engine. Dropping the id needs the engine too, so the entry must be
+    /// leaked.
+    pub(crate) fn new(idx: InventoryIdx, uses: EntryUses) -> Self {
+        Self {
+            // SAFETY: an all zero `StringName` is the empty name, and zeroing
+            // its opaque bytes does not call into the engine.
+            id: unsafe { std::mem::zeroed() },
+            idx,
+            uses,
+        }
+    }
 }
 
 impl GodotConvert for SlotEntry {
@@ -76,6 +92,50 @@ impl InventorySlot {
             None
         }
     }
+
+    /// Whether the slot holds an entry the unit can have equipped.
+    pub(crate) fn holds_equipment(&self) -> bool {
+        self.contains_equipment() && !self.is_empty()
+    }
+
+    /// Consumes `uses` of the entry, clearing it if it runs out of uses.
+    /// Returns the entry if it was cleared.
+    pub(crate) fn consume_uses(&mut self, uses: u8) -> Option<SlotEntry> {
+        if self.slot_entry.as_mut()?.uses.consume(uses) {
+            self.slot_entry.take()
+        } else {
+            None
+        }
+    }
+}
+
+/// Slot equipped by default, the first of `slots` holding equipment or `-1`
+/// if there is none.
+pub(crate) fn default_equipped_slot(slots: &[InventorySlot]) -> i8 {
+    slots
+        .iter()
+        .position(InventorySlot::holds_equipment)
+        .map_or(-1, |slot_idx| slot_idx as i8)
+}
+
+/// Consumes `uses` of the entry in `slot_idx` of `slots`, clearing it if it
+/// runs out of uses. If the cleared slot was `equipped_slot_idx`, the
+/// `default_equipped_slot` is equipped instead.
+///
+/// Returns the cleared entry.
+pub(crate) fn consume_slot_uses_in(
+    slots: &mut [InventorySlot],
+    equipped_slot_idx: &mut i8,
+    slot_idx: usize,
+    uses: u8,
+) -> Option<SlotEntry> {
+    let broken_entry = slots.get_mut(slot_idx)?.consume_uses(uses)?;
+
+    if *equipped_slot_idx == slot_idx as i8 {
+        *equipped_slot_idx = default_equipped_slot(slots);
+    }
+
+    Some(broken_entry)
 }
 
 impl GodotConvert for InventorySlot {
@@ -122,3 +182,128 @@ impl FromGodot for InventorySlot {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::mem::ManuallyDrop;
+
+    // Slots are never dropped, as dropping their entries needs the engine.
+    fn slot(slot_type: SlotType, idx: InventoryIdx, uses: EntryUses) -> InventorySlot {
+        InventorySlot {
+            slot_type,
+            slot_entry: Some(SlotEntry::new(idx, uses)),
+        }
+    }
+
+    fn leak_idx(entry: Option<SlotEntry>) -> Option<InventoryIdx> {
+        entry.map(|entry| ManuallyDrop::new(entry).idx)
+    }
+
+    fn entry_uses(slot: &InventorySlot) -> Option<EntryUses> {
+        slot.get_entry().map(|entry| entry.uses)
+    }
+
+    mod consume_uses {
+        use super::*;
+
+        #[test]
+        fn finite_entries_are_cleared_once_out_of_uses() {
+            let mut sword = ManuallyDrop::new(slot(SlotType::Physical, 7, EntryUses::Finite(2)));
+
+            assert_eq!(leak_idx(sword.consume_uses(1)), None);
+            assert_eq!(entry_uses(&sword), Some(EntryUses::Finite(1)));
+
+            assert_eq!(leak_idx(sword.consume_uses(3)), Some(7));
+            assert!(sword.is_empty());
+            assert_eq!(leak_idx(sword.consume_uses(1)), None);
+        }
+
+        #[test]
+        fn infinite_and_no_uses_entries_are_kept() {
+            for uses in [EntryUses::Infinite, EntryUses::NoUses] {
+                let mut entry_slot = ManuallyDrop::new(slot(SlotType::Item, 3, uses));
+
+                assert_eq!(leak_idx(entry_slot.consume_uses(u8::MAX)), None);
+                assert_eq!(entry_uses(&entry_slot), Some(uses));
+            }
+        }
+    }
+
+    mod consume_slot_uses_in {
+        use super::*;
+
+        fn slots() -> ManuallyDrop<[InventorySlot; 4]> {
+            ManuallyDrop::new([
+                slot(SlotType::Physical, 10, EntryUses::Finite(1)),
+                slot(SlotType::Item, 11, EntryUses::Finite(1)),
+                slot(SlotType::Magical, 12, EntryUses::Finite(5)),
+                slot(SlotType::Physical, 13, EntryUses::Infinite),
+            ])
+        }
+
+        #[test]
+        fn broken_equipped_slot_moves_to_the_next_equipment() {
+            let mut slots = slots();
+            let mut equipped_slot_idx = 0;
+
+            let broken_idx = leak_idx(consume_slot_uses_in(
+                &mut *slots,
+                &mut equipped_slot_idx,
+                0,
+                1,
+            ));
+
+            assert_eq!(broken_idx, Some(10));
+            assert!(slots[0].is_empty());
+            assert_eq!(equipped_slot_idx, 2);
+        }
+
+        #[test]
+        fn other_slots_keep_the_equipped_slot() {
+            let mut slots = slots();
+            let mut equipped_slot_idx = 2;
+
+            assert_eq!(
+                leak_idx(consume_slot_uses_in(
+                    &mut *slots,
+                    &mut equipped_slot_idx,
+                    1,
+                    1
+                )),
+                Some(11)
+            );
+            assert_eq!(equipped_slot_idx, 2);
+
+            // Equipped entries with uses left stay equipped.
+            assert_eq!(
+                leak_idx(consume_slot_uses_in(
+                    &mut *slots,
+                    &mut equipped_slot_idx,
+                    2,
+                    1
+                )),
+                None
+            );
+            assert_eq!(entry_uses(&slots[2]), Some(EntryUses::Finite(4)));
+            assert_eq!(equipped_slot_idx, 2);
+        }
+
+        #[test]
+        fn equipped_slot_is_cleared_without_equipment_left() {
+            let mut slots = ManuallyDrop::new([slot(SlotType::Support, 4, EntryUses::Finite(1))]);
+            let mut equipped_slot_idx = 0;
+
+            assert_eq!(
+                leak_idx(consume_slot_uses_in(
+                    &mut *slots,
+                    &mut equipped_slot_idx,
+                    0,
+                    1
+                )),
+                Some(4)
+            );
+            assert_eq!(equipped_slot_idx, -1);
+        }
+    }
+}
@@ -2,6 +2,7 @@ use crate::database::{
     DbConnector,
     inventory::{EntryUses, InventoryId, SlotType},
     kit::KitId,
+    movement_class::MovementClassId,
     role::RoleId,
     skill::SkillId,
     unit::{UnitEntry, UnitId},
@@ -24,9 +25,9 @@ pub(crate) type InventoryIdx = u32;
 pub(crate) struct UnitData {
     // Identifiers
     #[export]
-    unit_id: UnitId,
+    pub(crate) unit_id: UnitId,
     #[export]
-    unit_idx: UnitIdx,
+    pub(crate) unit_idx: UnitIdx,
     // Unit display data
     #[export]
     display_name: StringName,
@@ -77,18 +78,21 @@ pub(crate) struct UnitData {
     mod_mov: i8,
     // Unit combat data - Consumable Stats
     #[export]
-    current_htp: u8,
+    pub(crate) current_htp: u8,
     // Unit combat data - Role/Kit data
     #[export]
     active_role_id: RoleId,
     #[export]
     active_kit_id: KitId,
+    // Movement class resolved from the active role's tags
+    #[export]
+    pub(crate) movement_class: MovementClassId,
     // Unit combat data - Skill data
     personal_skill_id: Option<SkillId>,
     equipped_skill_ids: Vec<SkillId>,
     // Unit combat data - Inventory
     // Array for ease of indexing
-    inventory_slots: [InventorySlot; 6],
+    pub(crate) inventory_slots: [InventorySlot; 6],
     // Current slot limits for eequipped kit
     #[export]
     max_physical_slots: u8,
@@ -100,12 +104,12 @@ pub(crate) struct UnitData {
     max_item_slots: u8,
     // Index of the equipment_slot equipped
     #[export]
-    equipped_slot_idx: i8,
+    pub(crate) equipped_slot_idx: i8,
     // Unit combat data - Interaction ranges
     #[export]
-    attack_range: Vector2i,
+    pub(crate) attack_range: Vector2i,
     #[export]
-    support_range: Vector2i,
+    pub(crate) support_range: Vector2i,
 }
 
 impl UnitData {
@@ -164,15 +168,57 @@ impl UnitData {
         true
     }
 
-    fn recompute_equipped_slot(&mut self) {
-        self.equipped_slot_idx = -1;
+    /// Consumes `uses` of the entry in `slot_idx`, removing it from the
+    /// inventory if it breaks. Returns the item index of the broken entry.
+    pub(crate) fn consume_slot_uses(
+        &mut self,
+        slot_idx: usize,
+        uses: u8,
+        db_link: GdRef<DbConnector>,
+    ) -> Option<InventoryIdx> {
+        let broken_idx = consume_slot_uses_in(
+            &mut self.inventory_slots,
+            &mut self.equipped_slot_idx,
+            slot_idx,
+            uses,
+        )?
+        .idx;
 
-        if let Some(slot_idx) = self
-            .inventory_slots
-            .iter()
-            .position(|slot| slot.contains_equipment() && !slot.is_empty())
-        {
-            self.equipped_slot_idx = slot_idx as i8;
+        if !self.try_recompute_ranges(db_link) {
+            godot_error!(
+                "Failed to recompute ranges of unit [{}] after breaking [{}]!",
+                self.unit_id,
+                broken_idx
+            );
+        }
+
+        Some(broken_idx)
+    }
+
+    fn recompute_movement_class(&mut self, db_link: &GdRef<DbConnector>) {
+        if let Some(class_id) = db_link.movement_class_for(&self.active_role_id) {
+            self.movement_class = class_id.clone();
+        } else {
+            godot_warn!(
+                "Movement class not found for role [{}]",
+                &self.active_role_id
+            );
+            self.movement_class = MovementClassId::default();
         }
     }
+
+    fn saturate_stat(value: i32) -> u8 {
+        value.clamp(0, u8::MAX as i32) as u8
+    }
+
+    /// Identifiers of the personal and equipped skills of the unit.
+    pub(crate) fn iter_skill_ids(&self) -> impl Iterator<Item = &SkillId> {
+        self.personal_skill_id
+            .iter()
+            .chain(self.equipped_skill_ids.iter())
+    }
+
+    fn recompute_equipped_slot(&mut self) {
+        self.equipped_slot_idx = default_equipped_slot(&self.inventory_slots);
+    }
 }
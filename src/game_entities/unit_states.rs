@@ -1,9 +1,10 @@
 use super::{
     army_states::ArmyStates,
+    range_cache::RangeCache,
     unit_data::{UnitData, UnitIdx},
 };
 use crate::{
-    database::{army::ArmyId, personality::PersonalityId},
+    database::{army::ArmyId, chapter::DefendRadii, personality::PersonalityId},
     traits::FromGstringVariant,
 };
 
@@ -14,7 +15,7 @@ type UnitSet = BTreeSet<UnitIdx>;
 
 #[derive(GodotClass, Default, Clone)]
 #[class(no_init, base=RefCounted)]
-struct UnitStates {
+pub(crate) struct UnitStates {
     pub(crate) unit_idx_to_army_id: HashMap<UnitIdx, ArmyId>,
     pub(crate) army_units: HashMap<ArmyId, UnitSet>,
     pub(crate) defeated_units: HashMap<ArmyId, UnitSet>,
@@ -23,6 +24,10 @@ struct UnitStates {
     pub(crate) grid_cell_to_idx: HashMap<Vector2i, UnitIdx>,
     pub(crate) unit_personalities: HashMap<UnitIdx, PersonalityId>,
     pub(crate) unit_defend_cells: HashMap<UnitIdx, Vector2i>,
+    /// Units without an entry use the default `DefendRadii`
+    pub(crate) unit_defend_radii: HashMap<UnitIdx, DefendRadii>,
+    /// Movement and interaction ranges computed by `RustPathfinder`
+    pub(crate) range_cache: RangeCache,
 }
 
 impl UnitStates {
@@ -35,6 +40,7 @@ impl UnitStates {
         unit_idx_to_cell: &Dictionary,
         unit_personalities: &Dictionary,
         unit_defend_cells: &Dictionary,
+        unit_defend_radii: &Dictionary,
     ) -> bool {
         use std::collections::HashSet;
 
@@ -75,6 +81,15 @@ impl UnitStates {
             is_valid = false;
         }
 
+        for (unit_idx, _) in unit_defend_radii.iter_shared() {
+            if !unit_defend_cells.contains_key(unit_idx) {
+                godot_error!(
+                    "UnitStates 'unit_defend_radii' unit_idx key not present in 'unit_defend_cells'!"
+                );
+                is_valid = false;
+            }
+        }
+
         let unit_data_idxs = data_store
             .iter_shared()
             .map(|(k, _)| UnitIdx::from_variant(&k))
@@ -172,6 +187,36 @@ impl UnitStates {
 
         is_valid
     }
+
+    /// Copies the states without their cached ranges, for simulations that
+    /// move units around and would invalidate them anyway.
+    pub(crate) fn clone_without_ranges(&self) -> Self {
+        let Self {
+            unit_idx_to_army_id,
+            army_units,
+            defeated_units,
+            data_store,
+            unit_idx_to_cell,
+            grid_cell_to_idx,
+            unit_personalities,
+            unit_defend_cells,
+            unit_defend_radii,
+            range_cache: _,
+        } = self;
+
+        Self {
+            unit_idx_to_army_id: unit_idx_to_army_id.clone(),
+            army_units: army_units.clone(),
+            defeated_units: defeated_units.clone(),
+            data_store: data_store.clone(),
+            unit_idx_to_cell: unit_idx_to_cell.clone(),
+            grid_cell_to_idx: grid_cell_to_idx.clone(),
+            unit_personalities: unit_personalities.clone(),
+            unit_defend_cells: unit_defend_cells.clone(),
+            unit_defend_radii: unit_defend_radii.clone(),
+            range_cache: RangeCache::default(),
+        }
+    }
 }
 
 #[godot_api]
@@ -184,6 +229,11 @@ impl UnitStates {
     /// * **data_store**: `{<unit_idx>: <unit_data: UnitData>}`
     /// * **unit_idx_to_cell**: `{<unit_idx>: <unit_cell: Vector2i>}`
     /// * **unit_personalities**: `{<unit_idx>: <personality_id: PersonalityId>}`
+    /// * **unit_defend_cells**: `{<unit_idx>: <defend_cell: Vector2i>}`
+    /// * **unit_defend_radii**: `{<unit_idx>: {leash_radius: <radius>, aggro_radius: <radius>}}`
+    ///
+    /// `unit_defend_radii` can be empty, an `aggro_radius` of -1 means every
+    /// hostile unit in reach is engaged.
     ///
     /// Will return **null** if the validation of the parameters **didn't succeed!**
     #[func]
@@ -194,6 +244,7 @@ impl UnitStates {
         unit_idx_to_cell: Dictionary,
         unit_personalities: Dictionary,
         unit_defend_cells: Dictionary,
+        unit_defend_radii: Dictionary,
     ) -> Option<Gd<Self>> {
         if !Self::validate_state_params(
             &army_units,
@@ -202,6 +253,7 @@ impl UnitStates {
             &unit_idx_to_cell,
             &unit_personalities,
             &unit_defend_cells,
+            &unit_defend_radii,
         ) {
             return None;
         }
@@ -267,6 +319,13 @@ impl UnitStates {
             states.unit_defend_cells.insert(idx, cell);
         }
 
+        for (unit_idx, defend_radii) in unit_defend_radii.iter_shared() {
+            let idx = UnitIdx::from_variant(&unit_idx);
+            let radii = DefendRadii::from_variant(&defend_radii);
+
+            states.unit_defend_radii.insert(idx, radii);
+        }
+
         Some(Gd::from_object(states))
     }
 
@@ -280,9 +339,11 @@ impl UnitStates {
         } else {
             if let Some(old_cell) = self.unit_idx_to_cell.insert(unit_idx, new_cell) {
                 let _ = self.grid_cell_to_idx.remove(&old_cell);
+                self.range_cache.invalidate_cells(&[old_cell]);
             }
 
             let _ = self.grid_cell_to_idx.insert(new_cell, unit_idx);
+            self.range_cache.invalidate_cells(&[new_cell]);
 
             true
         }
@@ -300,7 +361,22 @@ impl UnitStates {
 
         if let Some(defeated_at) = self.unit_idx_to_cell.remove(&unit_idx) {
             self.grid_cell_to_idx.remove(&defeated_at);
+            self.range_cache.invalidate_cells(&[defeated_at]);
         }
+
+        self.range_cache.remove(unit_idx);
+    }
+
+    /// Drops the cached ranges of `unit_idx`, needed when changes that are not
+    /// tracked by the cache, like army relations, affect them.
+    #[func]
+    fn invalidate_ranges_for(&mut self, unit_idx: UnitIdx) {
+        self.range_cache.remove(unit_idx);
+    }
+
+    #[func]
+    fn clear_range_cache(&mut self) {
+        self.range_cache.clear();
     }
 
     #[func]
@@ -318,21 +394,11 @@ impl UnitStates {
                 if unit_idx == *other_idx {
                     false
                 } else {
-                    let army_states = army_states_link.bind();
                     let other_army_id = &self.unit_idx_to_army_id[other_idx];
 
-                    if unit_army_id == other_army_id {
-                        false
-                    } else if unit_army_id == &army_states.player_army
-                        || army_states.allied_armies.contains(unit_army_id)
-                    {
-                        army_states.enemy_armies.contains(other_army_id)
-                    } else if army_states.enemy_armies.contains(unit_army_id) {
-                        army_states.allied_armies.contains(other_army_id)
-                            || other_army_id == &army_states.player_army
-                    } else {
-                        false
-                    }
+                    army_states_link
+                        .bind()
+                        .are_hostile(unit_army_id, other_army_id)
                 }
             }
             _ => false,
@@ -354,21 +420,11 @@ impl UnitStates {
                 if unit_idx == *other_idx {
                     false
                 } else {
-                    let army_states = army_states_link.bind();
                     let other_army_id = &self.unit_idx_to_army_id[other_idx];
 
-                    if unit_army_id == other_army_id {
-                        true
-                    } else if unit_army_id == &army_states.player_army {
-                        army_states.allied_armies.contains(other_army_id)
-                    } else if army_states.allied_armies.contains(unit_army_id) {
-                        army_states.allied_armies.contains(other_army_id)
-                            || other_army_id == &army_states.player_army
-                    } else if army_states.enemy_armies.contains(unit_army_id) {
-                        army_states.enemy_armies.contains(other_army_id)
-                    } else {
-                        false
-                    }
+                    army_states_link
+                        .bind()
+                        .are_friendly(unit_army_id, other_army_id)
                 }
             }
             _ => false,
@@ -556,4 +612,16 @@ impl UnitStates {
             .copied()
             .unwrap_or(Vector2i::new(-1, -1))
     }
+
+    /// Returns the defend radii associated with 'unit_idx', with the same
+    /// structure used in `try_from_state`.
+    /// Returns the default radii if 'unit_idx' has none set.
+    #[func]
+    fn get_defend_radii_for(&self, unit_idx: UnitIdx) -> Dictionary {
+        self.unit_defend_radii
+            .get(&unit_idx)
+            .copied()
+            .unwrap_or_default()
+            .to_godot()
+    }
 }
@@ -1,5 +1,7 @@
 use godot::prelude::*;
 
+mod ai;
+mod combat;
 pub(crate) mod database;
 mod game_entities;
 mod pathfinding;
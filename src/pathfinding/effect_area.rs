@@ -0,0 +1,131 @@
+use super::{GridTopology, RustPathfinder};
+use crate::database::inventory::AreaShape;
+
+use godot::prelude::*;
+use std::collections::HashSet;
+
+/// Tolerance used when comparing directions, so cells lying exactly on the
+/// edge of a cone are included.
+const DIRECTION_EPSILON: f32 = 1e-4;
+
+impl RustPathfinder {
+    /// Computes the cells affected by an effect with the `area` shape, cast
+    /// from `caster` and aimed at `aim_cell`, limited to `grid_bounds`.
+    /// `aim_occupied` tells whether a unit stands on `aim_cell`.
+    ///
+    /// Directional shapes return no cells if `aim_cell` is the `caster` cell,
+    /// and `Splash` returns no cells if `aim_cell` is not occupied.
+    pub(crate) fn effect_area_with(
+        area: AreaShape,
+        caster: Vector2i,
+        aim_cell: Vector2i,
+        aim_occupied: bool,
+        grid_bounds: Rect2i,
+        topology: GridTopology,
+    ) -> HashSet<Vector2i> {
+        let size = area.size() as i32;
+
+        let mut area_cells = match area {
+            AreaShape::Splash(_) if !aim_occupied => HashSet::new(),
+            AreaShape::Diamond(_) | AreaShape::Splash(_) => {
+                Self::action_range_with([aim_cell], Vector2i::new(0, size), grid_bounds, topology)
+            }
+            AreaShape::Square(_) => {
+                let square_topology = match topology {
+                    GridTopology::Square4 | GridTopology::Square8 => GridTopology::Square8,
+                    hex_topology => hex_topology,
+                };
+
+                Self::action_range_with(
+                    [aim_cell],
+                    Vector2i::new(0, size),
+                    grid_bounds,
+                    square_topology,
+                )
+            }
+            AreaShape::Cross(_) => {
+                // Crosses follow the axes of the grid, so diagonals are left out.
+                let cross_topology = match topology {
+                    GridTopology::Square4 | GridTopology::Square8 => GridTopology::Square4,
+                    hex_topology => hex_topology,
+                };
+
+                (0..cross_topology.directions(aim_cell).len())
+                    .flat_map(|direction_idx| {
+                        Self::cells_in_direction(aim_cell, direction_idx, size, cross_topology)
+                    })
+                    .chain(std::iter::once(aim_cell))
+                    .collect()
+            }
+            AreaShape::Line(_) => Self::direction_towards(caster, aim_cell, topology)
+                .map(|direction_idx| {
+                    Self::cells_in_direction(caster, direction_idx, size, topology).collect()
+                })
+                .unwrap_or_default(),
+            AreaShape::Cone(_) => Self::direction_towards(caster, aim_cell, topology)
+                .map(|direction_idx| {
+                    let origin = topology.world_position(caster);
+                    let direction = (topology
+                        .world_position(caster + topology.directions(caster)[direction_idx])
+                        - origin)
+                        .normalized();
+                    let min_alignment = topology.cone_half_angle().cos() - DIRECTION_EPSILON;
+
+                    Self::action_range_with([caster], Vector2i::new(1, size), grid_bounds, topology)
+                        .into_iter()
+                        .filter(|cell| {
+                            let cell_direction =
+                                (topology.world_position(*cell) - origin).normalized();
+                            cell_direction.dot(direction) >= min_alignment
+                        })
+                        .collect()
+                })
+                .unwrap_or_default(),
+        };
+
+        area_cells.retain(|cell| grid_bounds.contains_point(*cell));
+
+        area_cells
+    }
+
+    /// Returns the index of the direction of `topology` that points closest
+    /// to `target` when moving from `cell`, or `None` if both are the same.
+    fn direction_towards(
+        cell: Vector2i,
+        target: Vector2i,
+        topology: GridTopology,
+    ) -> Option<usize> {
+        if cell == target {
+            return None;
+        }
+
+        let origin = topology.world_position(cell);
+        let target_direction = (topology.world_position(target) - origin).normalized();
+
+        topology
+            .directions(cell)
+            .iter()
+            .map(|direction| (topology.world_position(cell + *direction) - origin).normalized())
+            .enumerate()
+            .min_by(|(_, lhs), (_, rhs)| {
+                rhs.dot(target_direction)
+                    .total_cmp(&lhs.dot(target_direction))
+            })
+            .map(|(direction_idx, _)| direction_idx)
+    }
+
+    /// Returns the `count` cells found stepping from `cell` following the
+    /// direction at `direction_idx`, without including `cell`.
+    fn cells_in_direction(
+        cell: Vector2i,
+        direction_idx: usize,
+        count: i32,
+        topology: GridTopology,
+    ) -> impl Iterator<Item = Vector2i> {
+        std::iter::successors(Some(cell), move |current| {
+            Some(*current + topology.directions(*current)[direction_idx])
+        })
+        .skip(1)
+        .take(count.max(0) as usize)
+    }
+}
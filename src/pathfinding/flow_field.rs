@@ -0,0 +1,91 @@
+use godot::prelude::*;
+use std::collections::HashMap;
+
+/// Distance field holding the cost of reaching the closest goal cell from
+/// every cell that can reach one, any number of units can follow it towards
+/// the goals without running a search per unit.
+#[derive(GodotClass)]
+#[class(no_init, base=RefCounted)]
+pub(crate) struct FlowField {
+    pub(crate) distances: HashMap<Vector2i, i32>,
+    /// Next cell of the cheapest path towards the goals, goals have no entry
+    pub(crate) next_cells: HashMap<Vector2i, Vector2i>,
+}
+
+impl FlowField {
+    pub(crate) fn distance_at(&self, cell: Vector2i) -> Option<i32> {
+        self.distances.get(&cell).copied()
+    }
+
+    /// Returns the next cell of the cheapest path from `cell` towards the
+    /// goals, or `None` if `cell` is a goal or cannot reach any.
+    pub(crate) fn next_cell(&self, cell: Vector2i) -> Option<Vector2i> {
+        self.next_cells.get(&cell).copied()
+    }
+
+    /// Sorts the reachable `cells` by their distance to the closest goal, the
+    /// closest one first, cells that cannot reach any goal are discarded.
+    pub(crate) fn sorted_by_distance<I>(&self, cells: I) -> Vec<Vector2i>
+    where
+        I: IntoIterator<Item = Vector2i>,
+    {
+        let mut sorted_cells = cells
+            .into_iter()
+            .filter_map(|cell| Some((self.distance_at(cell)?, cell)))
+            .collect::<Vec<_>>();
+
+        sorted_cells.sort_unstable_by_key(|(distance, cell)| (*distance, cell.y, cell.x));
+
+        sorted_cells.into_iter().map(|(_, cell)| cell).collect()
+    }
+}
+
+#[godot_api]
+impl FlowField {
+    /// Tries to get the cost of reaching the closest goal from `cell`.
+    /// Returns -1 if `cell` cannot reach any goal.
+    #[func]
+    fn get_distance_at(&self, cell: Vector2i) -> i32 {
+        self.distance_at(cell).unwrap_or(-1)
+    }
+
+    #[func]
+    fn can_reach_goal(&self, cell: Vector2i) -> bool {
+        self.distances.contains_key(&cell)
+    }
+
+    /// Returns the next cell of the cheapest path from `cell` towards the goals.
+    /// Returns `cell` itself if it is a goal or cannot reach any.
+    #[func]
+    fn get_next_cell(&self, cell: Vector2i) -> Vector2i {
+        self.next_cell(cell).unwrap_or(cell)
+    }
+
+    /// Returns the cells followed from `cell` to the closest goal, both
+    /// included. Returns an empty array if `cell` cannot reach any goal.
+    #[func]
+    fn get_path_from(&self, cell: Vector2i) -> Array<Vector2i> {
+        if !self.distances.contains_key(&cell) {
+            return Array::new();
+        }
+
+        let mut path = array![cell];
+        let mut current = cell;
+
+        while let Some(next) = self.next_cell(current) {
+            path.push(next);
+            current = next;
+        }
+
+        path
+    }
+
+    /// Sorts `cells` by their distance to the closest goal, the closest one
+    /// first. Cells that cannot reach any goal are not returned.
+    #[func]
+    fn sort_by_distance(&self, cells: Array<Vector2i>) -> Array<Vector2i> {
+        self.sorted_by_distance(cells.iter_shared())
+            .into_iter()
+            .collect()
+    }
+}
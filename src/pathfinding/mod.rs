@@ -0,0 +1,1938 @@
+use crate::{
+    database::{army::ArmyId, inventory::AreaShape},
+    game_entities::{
+        army_states::ArmyStates,
+        range_cache::{CachedRanges, RangeKey},
+        unit_data::UnitIdx,
+        unit_states::UnitStates,
+    },
+};
+
+use godot::prelude::*;
+use priority_queue::PriorityQueue;
+use std::{
+    cmp::Reverse,
+    collections::{HashMap, HashSet},
+    vec::Vec,
+};
+
+mod effect_area;
+mod flow_field;
+mod movement_rules;
+mod terrain_grid;
+mod topology;
+
+pub(crate) use flow_field::*;
+pub(crate) use movement_rules::*;
+pub(crate) use terrain_grid::*;
+pub(crate) use topology::*;
+
+#[derive(GodotClass)]
+#[class(no_init)]
+pub(crate) struct RustPathfinder;
+
+/// Cell from which a unit can interact with a target.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) struct AttackCell {
+    pub(crate) cell: Vector2i,
+    pub(crate) path_cost: i32,
+    pub(crate) bonus: TerrainBonus,
+}
+
+#[godot_api]
+impl RustPathfinder {
+    #[func]
+    fn compute_movement_range(
+        origin: Vector2i,
+        unit_move_range: i32,
+        unit_move_multiplier: i32,
+        unit_army_id: StringName,
+        grid_data: Variant,
+        unit_states: Variant,
+        cost_function: Callable,
+    ) -> Array<Vector2i> {
+        let grid_bounds = Rect2i::from_variant(&grid_data.call("get_grid_bounds", &[]));
+        let solid_nodes = Dictionary::from_variant(&grid_data.call("get_solid_nodes", &[]));
+        let topology = Self::topology_of(&grid_data);
+
+        if unit_move_range <= 0 || unit_move_multiplier <= 0 {
+            return array![origin];
+        }
+
+        let eff_move_range = unit_move_range * unit_move_multiplier;
+
+        Self::movement_costs_with(
+            origin,
+            eff_move_range,
+            grid_bounds,
+            topology,
+            |_| false,
+            |node, neighbour| {
+                if solid_nodes.contains_key(neighbour) {
+                    None
+                } else {
+                    let args = varray![node, neighbour, unit_army_id, grid_data, unit_states];
+                    Some(cost_function.callv(&args).to::<i32>())
+                }
+            },
+        )
+        .into_keys()
+        .collect()
+    }
+
+    /// Native counterpart of `compute_movement_range`, step costs are looked up
+    /// in `terrain_grid` using the cost table of `movement_class`.
+    ///
+    /// Returns only `origin` if `movement_class` is not present in the grid.
+    #[func]
+    fn compute_grid_movement_range(
+        origin: Vector2i,
+        unit_move_range: i32,
+        unit_move_multiplier: i32,
+        movement_class: MovementClass,
+        terrain_grid: Gd<TerrainGrid>,
+    ) -> Array<Vector2i> {
+        if unit_move_range <= 0 || unit_move_multiplier <= 0 {
+            return array![origin];
+        }
+
+        let grid = terrain_grid.bind();
+
+        if let Some(costs) = grid.costs_for(&movement_class) {
+            let eff_move_range = unit_move_range * unit_move_multiplier;
+
+            Self::movement_costs_with(
+                origin,
+                eff_move_range,
+                grid.grid_bounds,
+                grid.topology,
+                |_| false,
+                |node, neighbour| grid.move_cost(costs, node, neighbour),
+            )
+            .into_keys()
+            .collect()
+        } else {
+            godot_error!("Movement class [{}] not found in grid!", movement_class);
+            array![origin]
+        }
+    }
+
+    /// Computes the cells `unit_idx` can end its movement on, using its
+    /// current position, movement and movement class.
+    ///
+    /// Cells of friendly units can be moved through but not stopped on, cells
+    /// of any other unit cannot be entered. If `zone_of_control` is **true**
+    /// entering a cell adjacent to an enemy unit ends the movement.
+    ///
+    /// Returns an empty array if the movement of `unit_idx` cannot be computed.
+    #[func]
+    fn compute_unit_movement_range(
+        unit_idx: UnitIdx,
+        unit_states: Gd<UnitStates>,
+        army_states: Gd<ArmyStates>,
+        terrain_grid: Gd<TerrainGrid>,
+        zone_of_control: bool,
+    ) -> Array<Vector2i> {
+        Self::placed_unit_movement_costs(
+            unit_idx,
+            &unit_states.bind(),
+            &army_states.bind(),
+            &terrain_grid.bind(),
+            zone_of_control,
+        )
+        .map(|move_costs| move_costs.into_keys().collect())
+        .unwrap_or_default()
+    }
+
+    /// Computes every cell `unit_idx` can move to and interact with `target_idx`
+    /// from, using the unit's `support_range` if the target is friendly and its
+    /// `attack_range` otherwise. Movement follows the same rules as in
+    /// `compute_unit_movement_range`.
+    ///
+    /// Cells are ranked by defense bonus, avoid bonus and path cost, the best
+    /// one first, and each entry has the following structure:
+    ///```
+    /// {
+    ///     cell: <cell>,
+    ///     cost: <path_cost>,
+    ///     avo_bonus: <terrain_avo_bonus>,
+    ///     def_bonus: <terrain_def_bonus>,
+    /// }
+    ///```
+    #[func]
+    fn compute_attack_cells(
+        unit_idx: UnitIdx,
+        target_idx: UnitIdx,
+        unit_states: Gd<UnitStates>,
+        army_states: Gd<ArmyStates>,
+        terrain_grid: Gd<TerrainGrid>,
+        zone_of_control: bool,
+    ) -> Array<Dictionary> {
+        let states = unit_states.bind();
+        let armies = army_states.bind();
+        let grid = terrain_grid.bind();
+
+        let (Some(target_cell), Some(unit_range)) = (
+            states.unit_idx_to_cell.get(&target_idx).copied(),
+            Self::interaction_range(unit_idx, target_idx, &states, &armies),
+        ) else {
+            godot_error!(
+                "Could not resolve interaction of unit [{}] with [{}]!",
+                unit_idx,
+                target_idx
+            );
+            return Array::new();
+        };
+
+        let Some(move_costs) =
+            Self::placed_unit_movement_costs(unit_idx, &states, &armies, &grid, zone_of_control)
+        else {
+            return Array::new();
+        };
+
+        Self::attack_cells_with(&move_costs, target_cell, unit_range, &grid)
+            .into_iter()
+            .map(|attack_cell| {
+                dict! {
+                    "cell": attack_cell.cell,
+                    "cost": attack_cell.path_cost,
+                    "avo_bonus": attack_cell.bonus.avo,
+                    "def_bonus": attack_cell.bonus.def,
+                }
+            })
+            .collect()
+    }
+
+    /// Computes every unit `unit_idx` can interact with after moving, hostile
+    /// units within its `attack_range` and friendly units within its
+    /// `support_range`. Movement follows the same rules as in
+    /// `compute_unit_movement_range`.
+    ///
+    /// Returns the unit indexes sorted in ascending order.
+    #[func]
+    fn compute_unit_targets(
+        unit_idx: UnitIdx,
+        unit_states: Gd<UnitStates>,
+        army_states: Gd<ArmyStates>,
+        terrain_grid: Gd<TerrainGrid>,
+        zone_of_control: bool,
+    ) -> Array<UnitIdx> {
+        let states = unit_states.bind();
+        let armies = army_states.bind();
+        let grid = terrain_grid.bind();
+
+        let Some(move_costs) =
+            Self::placed_unit_movement_costs(unit_idx, &states, &armies, &grid, zone_of_control)
+        else {
+            return Array::new();
+        };
+
+        let mut targets = states
+            .unit_idx_to_cell
+            .iter()
+            .filter(|(target_idx, _)| **target_idx != unit_idx)
+            .filter_map(|(target_idx, target_cell)| {
+                let unit_range = Self::interaction_range(unit_idx, *target_idx, &states, &armies)?;
+
+                move_costs
+                    .keys()
+                    .any(|cell| {
+                        let distance = grid.topology.distance(*cell, *target_cell);
+                        distance >= unit_range.x && distance <= unit_range.y
+                    })
+                    .then_some(*target_idx)
+            })
+            .collect::<Vec<_>>();
+
+        targets.sort_unstable();
+
+        targets.into_iter().collect()
+    }
+
+    #[func]
+    fn compute_action_range(
+        move_visited: Array<Vector2i>,
+        unit_range: Vector2i,
+        grid_bounds: Rect2i,
+    ) -> Array<Vector2i> {
+        Self::action_range_with(
+            move_visited.iter_shared(),
+            unit_range,
+            grid_bounds,
+            GridTopology::Square4,
+        )
+        .into_iter()
+        .collect()
+    }
+
+    /// Counterpart of `compute_action_range` that measures distances using
+    /// the topology of `terrain_grid`.
+    #[func]
+    fn compute_grid_action_range(
+        move_visited: Array<Vector2i>,
+        unit_range: Vector2i,
+        terrain_grid: Gd<TerrainGrid>,
+    ) -> Array<Vector2i> {
+        let grid = terrain_grid.bind();
+
+        Self::action_range_with(
+            move_visited.iter_shared(),
+            unit_range,
+            grid.grid_bounds,
+            grid.topology,
+        )
+        .into_iter()
+        .collect()
+    }
+
+    /// Computes the union of every cell that any unit of `army_id` can attack
+    /// next turn, taking into account each unit's movement class, current
+    /// movement and attack range.
+    ///
+    /// Movement follows the same rules as in `compute_unit_movement_range`,
+    /// and cells occupied by other units are not considered as positions to
+    /// attack from.
+    #[func]
+    fn compute_danger_zone(
+        army_id: ArmyId,
+        unit_states: Gd<UnitStates>,
+        army_states: Gd<ArmyStates>,
+        terrain_grid: Gd<TerrainGrid>,
+        zone_of_control: bool,
+    ) -> Array<Vector2i> {
+        Self::danger_zone_of(
+            &army_id,
+            &unit_states.bind(),
+            &army_states.bind(),
+            &terrain_grid.bind(),
+            zone_of_control,
+        )
+        .into_iter()
+        .collect()
+    }
+
+    /// Returns the movement, attack and support ranges of `unit_idx`, reusing
+    /// the ones cached in `unit_states` while no change in the map within the
+    /// unit's reach invalidated them. Movement follows the same rules as in
+    /// `compute_unit_movement_range`.
+    ///
+    /// The returned dictionary has the following structure:
+    ///```
+    /// {
+    ///     movement: [<cell>, ..],
+    ///     attack: [<cell>, ..],
+    ///     support: [<cell>, ..],
+    /// }
+    ///```
+    /// All the arrays are empty if the movement of `unit_idx` cannot be computed.
+    #[func]
+    fn get_cached_ranges(
+        unit_idx: UnitIdx,
+        mut unit_states: Gd<UnitStates>,
+        army_states: Gd<ArmyStates>,
+        terrain_grid: Gd<TerrainGrid>,
+        zone_of_control: bool,
+    ) -> Dictionary {
+        let grid_id = terrain_grid.instance_id();
+        let grid = terrain_grid.bind();
+
+        let ranges = {
+            let states = unit_states.bind();
+
+            let Some(key) = Self::range_key(unit_idx, &states, grid_id, zone_of_control) else {
+                godot_error!("Unit [{}] is not placed in the map!", unit_idx);
+                return Self::ranges_to_dictionary(None);
+            };
+
+            if let Some(cached) = states.range_cache.get(unit_idx, &key, &grid) {
+                return Self::ranges_to_dictionary(Some(cached));
+            }
+
+            let Some((origin, mut move_costs, rules)) = Self::placed_unit_entered_costs(
+                unit_idx,
+                &states,
+                &army_states.bind(),
+                &grid,
+                zone_of_control,
+            ) else {
+                return Self::ranges_to_dictionary(None);
+            };
+
+            // The reach includes the cells only moved through, so moving any
+            // unit along the way invalidates the cached ranges.
+            let reach = Self::movement_reach_with(
+                move_costs.keys().copied(),
+                grid.grid_bounds,
+                grid.topology,
+            );
+
+            Self::retain_stopping_cells(&mut move_costs, origin, &rules);
+
+            let interaction_cells = |unit_range: Vector2i| {
+                if unit_range > Vector2i::ZERO {
+                    Self::action_range_with(
+                        move_costs.keys().copied(),
+                        unit_range,
+                        grid.grid_bounds,
+                        grid.topology,
+                    )
+                } else {
+                    HashSet::new()
+                }
+            };
+
+            let attack_cells = interaction_cells(key.attack_range);
+            let support_cells = interaction_cells(key.support_range);
+
+            CachedRanges {
+                key,
+                grid_revision: grid.revision,
+                move_costs,
+                attack_cells,
+                support_cells,
+                reach,
+            }
+        };
+
+        let ranges_dict = Self::ranges_to_dictionary(Some(&ranges));
+
+        unit_states.bind_mut().range_cache.insert(unit_idx, ranges);
+
+        ranges_dict
+    }
+
+    /// Computes the cells affected by an effect with the `area_shape` pattern,
+    /// cast from `caster` and aimed at `aim_cell`, using the topology and
+    /// bounds of `terrain_grid`.
+    ///
+    /// Centered shapes are placed around `aim_cell`, while `Line` and `Cone`
+    /// start next to `caster` and only use `aim_cell` to pick their direction.
+    /// `Splash` only affects any cell if a unit of `unit_states` stands on
+    /// `aim_cell`.
+    #[func]
+    fn compute_effect_area(
+        caster: Vector2i,
+        aim_cell: Vector2i,
+        area_shape: AreaShape,
+        unit_states: Gd<UnitStates>,
+        terrain_grid: Gd<TerrainGrid>,
+    ) -> Array<Vector2i> {
+        let grid = terrain_grid.bind();
+
+        Self::effect_area_with(
+            area_shape,
+            caster,
+            aim_cell,
+            unit_states.bind().grid_cell_to_idx.contains_key(&aim_cell),
+            grid.grid_bounds,
+            grid.topology,
+        )
+        .into_iter()
+        .collect()
+    }
+
+    /// Computes a `FlowField` towards `goal_cells` in a single pass, holding
+    /// the cost of reaching the closest goal from every cell using the cost
+    /// table of `movement_class`. Units are not taken into account.
+    ///
+    /// Will return **null** if `movement_class` is not present in the grid.
+    #[func]
+    fn compute_flow_field(
+        goal_cells: Array<Vector2i>,
+        movement_class: MovementClass,
+        terrain_grid: Gd<TerrainGrid>,
+    ) -> Option<Gd<FlowField>> {
+        let grid = terrain_grid.bind();
+
+        if let Some(costs) = grid.costs_for(&movement_class) {
+            Some(Gd::from_object(Self::flow_field_with(
+                goal_cells.iter_shared(),
+                grid.grid_bounds,
+                grid.topology,
+                |cell, neighbour| grid.move_cost(costs, cell, neighbour),
+            )))
+        } else {
+            godot_error!("Movement class [{}] not found in grid!", movement_class);
+            None
+        }
+    }
+
+    /// Same as `compute_flow_field` using the cells of every unit of `army_id`
+    /// as goal cells.
+    #[func]
+    fn compute_army_flow_field(
+        army_id: ArmyId,
+        unit_states: Gd<UnitStates>,
+        movement_class: MovementClass,
+        terrain_grid: Gd<TerrainGrid>,
+    ) -> Option<Gd<FlowField>> {
+        let goal_cells = {
+            let states = unit_states.bind();
+
+            states
+                .army_units
+                .get(&army_id)
+                .into_iter()
+                .flatten()
+                .filter_map(|unit_idx| states.unit_idx_to_cell.get(unit_idx).copied())
+                .collect::<Array<Vector2i>>()
+        };
+
+        Self::compute_flow_field(goal_cells, movement_class, terrain_grid)
+    }
+
+    /// Computes the cheapest path between `from` and `to` calling `cost_function`
+    /// to get the cost of every step, step costs are expected to be at least 1.
+    ///
+    /// If `to` cannot be reached the path only contains `from`.
+    #[func]
+    fn compute_movement_path(
+        from: Vector2i,
+        to: Vector2i,
+        unit_node: Variant,
+        grid_data: Variant,
+        army_manager: Variant,
+        valid_cells: Dictionary,
+        cost_function: Callable,
+    ) -> Array<Vector2i> {
+        Self::callable_movement_path(
+            from,
+            to,
+            unit_node,
+            grid_data,
+            army_manager,
+            valid_cells,
+            cost_function,
+        )
+        .map_or_else(
+            || Array::from(&[from]),
+            |(path, _)| path.into_iter().collect(),
+        )
+    }
+
+    /// Same as `compute_movement_path` also returning the cost of the path.
+    ///
+    /// The returned dictionary has the following structure:
+    ///```
+    /// {
+    ///     path: [<cell>, ..],
+    ///     cost: <path_cost>,
+    /// }
+    ///```
+    /// If `to` cannot be reached `path` is empty and `cost` is -1.
+    #[func]
+    fn compute_movement_path_with_cost(
+        from: Vector2i,
+        to: Vector2i,
+        unit_node: Variant,
+        grid_data: Variant,
+        army_manager: Variant,
+        valid_cells: Dictionary,
+        cost_function: Callable,
+    ) -> Dictionary {
+        Self::path_to_dictionary(Self::callable_movement_path(
+            from,
+            to,
+            unit_node,
+            grid_data,
+            army_manager,
+            valid_cells,
+            cost_function,
+        ))
+    }
+
+    /// Native counterpart of `compute_movement_path_with_cost`, step costs are
+    /// looked up in `terrain_grid` using the cost table of `movement_class`.
+    ///
+    /// Returns the same structure as `compute_movement_path_with_cost`.
+    #[func]
+    fn compute_grid_movement_path(
+        from: Vector2i,
+        to: Vector2i,
+        movement_class: MovementClass,
+        terrain_grid: Gd<TerrainGrid>,
+        valid_cells: Dictionary,
+    ) -> Dictionary {
+        if from == to {
+            return Self::path_to_dictionary(Some((vec![from], 0)));
+        }
+
+        let grid = terrain_grid.bind();
+
+        if let Some(costs) = grid.costs_for(&movement_class) {
+            let min_step_cost = TerrainGrid::min_step_cost(costs);
+
+            Self::path_to_dictionary(Self::movement_path_with(
+                from,
+                to,
+                grid.grid_bounds,
+                grid.topology,
+                min_step_cost,
+                |_| false,
+                |node, neighbour| {
+                    if valid_cells.contains_key(neighbour) {
+                        grid.move_cost(costs, node, neighbour)
+                    } else {
+                        None
+                    }
+                },
+            ))
+        } else {
+            godot_error!("Movement class [{}] not found in grid!", movement_class);
+            Self::path_to_dictionary(None)
+        }
+    }
+
+    /// Computes the cheapest path for `unit_idx` from its current position to
+    /// `to`, following the same movement rules as `compute_unit_movement_range`
+    /// but without limiting the path cost to the unit's movement.
+    ///
+    /// Returns the same structure as `compute_movement_path_with_cost`.
+    #[func]
+    fn compute_unit_movement_path(
+        unit_idx: UnitIdx,
+        to: Vector2i,
+        unit_states: Gd<UnitStates>,
+        army_states: Gd<ArmyStates>,
+        terrain_grid: Gd<TerrainGrid>,
+        zone_of_control: bool,
+    ) -> Dictionary {
+        let states = unit_states.bind();
+        let grid = terrain_grid.bind();
+
+        let (Some(from), Some(unit_data)) = (
+            states.unit_idx_to_cell.get(&unit_idx).copied(),
+            states.data_store.get(&unit_idx),
+        ) else {
+            godot_error!("Unit [{}] is not placed in the map!", unit_idx);
+            return Self::path_to_dictionary(None);
+        };
+
+        if from == to {
+            return Self::path_to_dictionary(Some((vec![from], 0)));
+        }
+
+        let Some(rules) = MovementRules::for_unit(
+            unit_idx,
+            &states,
+            &army_states.bind(),
+            zone_of_control,
+            &grid,
+        ) else {
+            godot_error!("Unit [{}] does not belong to any army!", unit_idx);
+            return Self::path_to_dictionary(None);
+        };
+
+        if !rules.can_stop_at(to) {
+            return Self::path_to_dictionary(None);
+        }
+
+        let unit = unit_data.bind();
+
+        if let Some(costs) = grid.costs_for(&unit.movement_class) {
+            Self::path_to_dictionary(Self::movement_path_with(
+                from,
+                to,
+                grid.grid_bounds,
+                grid.topology,
+                TerrainGrid::min_step_cost(costs),
+                |cell| rules.ends_movement_at(cell),
+                |node, neighbour| {
+                    if rules.can_enter(neighbour) {
+                        grid.move_cost(costs, node, neighbour)
+                    } else {
+                        None
+                    }
+                },
+            ))
+        } else {
+            godot_error!(
+                "Movement class [{}] not found in grid!",
+                &unit.movement_class
+            );
+            Self::path_to_dictionary(None)
+        }
+    }
+
+    #[func]
+    fn manhattan_distance(from: Vector2i, to: Vector2i) -> i32 {
+        (from.x - to.x).abs() + (from.y - to.y).abs()
+    }
+}
+
+impl RustPathfinder {
+    /// Computes the cheapest path between `from` and `to` together with its
+    /// cost, calling `cost_function` to get the cost of every step.
+    fn callable_movement_path(
+        from: Vector2i,
+        to: Vector2i,
+        unit_node: Variant,
+        grid_data: Variant,
+        army_manager: Variant,
+        valid_cells: Dictionary,
+        cost_function: Callable,
+    ) -> Option<(Vec<Vector2i>, i32)> {
+        if from == to {
+            return Some((vec![from], 0));
+        }
+
+        let grid_bounds = Rect2i::from_variant(&grid_data.call("get_grid_bounds", &[]));
+        let solid_nodes = Dictionary::from_variant(&grid_data.call("get_solid_nodes", &[]));
+        let topology = Self::topology_of(&grid_data);
+
+        Self::movement_path_with(
+            from,
+            to,
+            grid_bounds,
+            topology,
+            1,
+            |_| false,
+            |node, neighbour| {
+                if !valid_cells.contains_key(neighbour) || solid_nodes.contains_key(neighbour) {
+                    None
+                } else {
+                    let args = varray![node, neighbour, unit_node, grid_data, army_manager];
+                    Some(cost_function.callv(&args).to::<i32>())
+                }
+            },
+        )
+    }
+
+    fn get_neighbours(
+        cell: Vector2i,
+        grid_bounds: Rect2i,
+        topology: GridTopology,
+    ) -> impl Iterator<Item = Vector2i> {
+        if grid_bounds.contains_point(cell) {
+            topology.directions(cell)
+        } else {
+            &[]
+        }
+        .iter()
+        .map(move |item| cell + *item)
+        .filter(move |item| grid_bounds.contains_point(*item))
+    }
+
+    /// Returns the topology of `grid_data` if it provides one through
+    /// `get_topology`, like `TerrainGrid` does, or `Square4` otherwise.
+    fn topology_of(grid_data: &Variant) -> GridTopology {
+        match grid_data.try_to::<Gd<Object>>() {
+            Ok(grid_object) if grid_object.has_method("get_topology") => {
+                let topology = grid_data.call("get_topology", &[]);
+
+                GridTopology::try_from_variant(&topology).unwrap_or_else(|err| {
+                    godot_error!("Invalid grid topology [{}]: {}", topology, err);
+                    GridTopology::Square4
+                })
+            }
+            _ => GridTopology::Square4,
+        }
+    }
+
+    /// Computes the cheapest cost of reaching every cell within `eff_move_range`
+    /// of `origin`.
+    ///
+    /// `step_cost` receives the current cell and the neighbour to move into, and
+    /// returns the cost of the step or `None` if the neighbour cannot be entered.
+    /// Cells for which `ends_movement` returns **true** can be reached but not
+    /// moved out of, except for `origin`.
+    pub(crate) fn movement_costs_with<E, F>(
+        origin: Vector2i,
+        eff_move_range: i32,
+        grid_bounds: Rect2i,
+        topology: GridTopology,
+        ends_movement: E,
+        mut step_cost: F,
+    ) -> HashMap<Vector2i, i32>
+    where
+        E: Fn(Vector2i) -> bool,
+        F: FnMut(Vector2i, Vector2i) -> Option<i32>,
+    {
+        let mut move_queue = PriorityQueue::with_capacity((eff_move_range.max(0) as usize) << 2);
+        let mut move_visited = HashMap::new();
+
+        move_queue.push(origin, Reverse(0_i32));
+
+        while let Some((node, Reverse(path_cost))) = move_queue.pop() {
+            move_visited.insert(node, path_cost);
+
+            if node != origin && ends_movement(node) {
+                continue;
+            }
+
+            for neighbour in Self::get_neighbours(node, grid_bounds, topology) {
+                if move_visited.contains_key(&neighbour) {
+                    continue;
+                }
+
+                if let Some(cost_to_move) = step_cost(node, neighbour) {
+                    let neighbour_cost = cost_to_move + path_cost;
+
+                    if neighbour_cost <= eff_move_range {
+                        move_queue.push_increase(neighbour, Reverse(neighbour_cost));
+                    }
+                }
+            }
+        }
+
+        move_visited
+    }
+
+    /// Computes the cells a unit can end its movement on together with their
+    /// cost, applying `rules` on top of the `costs` table of `terrain_grid`.
+    pub(crate) fn unit_movement_costs_with(
+        origin: Vector2i,
+        eff_move_range: i32,
+        terrain_grid: &TerrainGrid,
+        costs: &[i32],
+        rules: &MovementRules,
+    ) -> HashMap<Vector2i, i32> {
+        let mut move_costs =
+            Self::unit_entered_costs_with(origin, eff_move_range, terrain_grid, costs, rules);
+
+        Self::retain_stopping_cells(&mut move_costs, origin, rules);
+
+        move_costs
+    }
+
+    /// Computes every cell a unit can enter together with their cost,
+    /// including the cells of friendly units it can only move through.
+    pub(crate) fn unit_entered_costs_with(
+        origin: Vector2i,
+        eff_move_range: i32,
+        terrain_grid: &TerrainGrid,
+        costs: &[i32],
+        rules: &MovementRules,
+    ) -> HashMap<Vector2i, i32> {
+        Self::movement_costs_with(
+            origin,
+            eff_move_range,
+            terrain_grid.grid_bounds,
+            terrain_grid.topology,
+            |cell| rules.ends_movement_at(cell),
+            |node, neighbour| {
+                if rules.can_enter(neighbour) {
+                    terrain_grid.move_cost(costs, node, neighbour)
+                } else {
+                    None
+                }
+            },
+        )
+    }
+
+    /// Drops from `move_costs` the cells a unit moving from `origin` cannot
+    /// end its movement on.
+    fn retain_stopping_cells(
+        move_costs: &mut HashMap<Vector2i, i32>,
+        origin: Vector2i,
+        rules: &MovementRules,
+    ) {
+        move_costs.retain(|cell, _| *cell == origin || rules.can_stop_at(*cell));
+    }
+
+    /// Computes the cells whose occupant or terrain affects a movement that
+    /// entered `entered_cells`, the cells themselves and their neighbours.
+    pub(crate) fn movement_reach_with(
+        entered_cells: impl IntoIterator<Item = Vector2i>,
+        grid_bounds: Rect2i,
+        topology: GridTopology,
+    ) -> HashSet<Vector2i> {
+        entered_cells
+            .into_iter()
+            .flat_map(|cell| {
+                Self::get_neighbours(cell, grid_bounds, topology).chain(std::iter::once(cell))
+            })
+            .collect()
+    }
+
+    /// Computes the cost of reaching the closest of `goals` from every cell
+    /// that can reach one, together with the next cell of the cheapest path,
+    /// running Dijkstra from all the goals at once.
+    ///
+    /// `step_cost` follows the same contract as in `movement_costs_with`, and
+    /// is called with the cells in the direction of the movement towards
+    /// the goals.
+    pub(crate) fn flow_field_with<I, F>(
+        goals: I,
+        grid_bounds: Rect2i,
+        topology: GridTopology,
+        mut step_cost: F,
+    ) -> FlowField
+    where
+        I: IntoIterator<Item = Vector2i>,
+        F: FnMut(Vector2i, Vector2i) -> Option<i32>,
+    {
+        let mut flow_queue = PriorityQueue::new();
+        let mut goal_costs = HashMap::new();
+        let mut distances = HashMap::new();
+        let mut next_cells = HashMap::new();
+
+        for goal in goals {
+            if grid_bounds.contains_point(goal) {
+                goal_costs.insert(goal, 0_i32);
+                flow_queue.push(goal, Reverse(0_i32));
+            }
+        }
+
+        while let Some((node, Reverse(goal_cost))) = flow_queue.pop() {
+            distances.insert(node, goal_cost);
+
+            for neighbour in Self::get_neighbours(node, grid_bounds, topology) {
+                if distances.contains_key(&neighbour) {
+                    continue;
+                }
+
+                if let Some(cost_to_move) = step_cost(neighbour, node) {
+                    let neighbour_cost = goal_cost + cost_to_move;
+
+                    if goal_costs
+                        .get(&neighbour)
+                        .is_none_or(|known_cost| neighbour_cost < *known_cost)
+                    {
+                        goal_costs.insert(neighbour, neighbour_cost);
+                        next_cells.insert(neighbour, node);
+                        flow_queue.push_increase(neighbour, Reverse(neighbour_cost));
+                    }
+                }
+            }
+        }
+
+        FlowField {
+            distances,
+            next_cells,
+        }
+    }
+
+    /// Computes every cell within `unit_range` distance of any of the `pivots`,
+    /// measured as the number of steps between cells in `topology`.
+    pub(crate) fn action_range_with<I>(
+        pivots: I,
+        unit_range: Vector2i,
+        grid_bounds: Rect2i,
+        topology: GridTopology,
+    ) -> HashSet<Vector2i>
+    where
+        I: IntoIterator<Item = Vector2i>,
+    {
+        let mut action_visited = HashSet::new();
+        let mut frontier = Vec::new();
+        let mut next_frontier = Vec::new();
+        let mut pivot_visited = HashSet::new();
+
+        for pivot in pivots {
+            frontier.clear();
+            pivot_visited.clear();
+
+            frontier.push(pivot);
+            pivot_visited.insert(pivot);
+
+            for distance in 0..=unit_range.y {
+                if distance >= unit_range.x {
+                    action_visited.extend(frontier.iter().copied());
+                }
+
+                if distance == unit_range.y {
+                    break;
+                }
+
+                for cell in frontier.drain(..) {
+                    for neighbour in Self::get_neighbours(cell, grid_bounds, topology) {
+                        if pivot_visited.insert(neighbour) {
+                            next_frontier.push(neighbour);
+                        }
+                    }
+                }
+
+                std::mem::swap(&mut frontier, &mut next_frontier);
+            }
+        }
+
+        action_visited
+    }
+
+    /// Computes the cheapest path between `from` and `to`, both included, using
+    /// A* with the `topology` distance scaled by `min_step_cost` as heuristic.
+    ///
+    /// `ends_movement` and `step_cost` follow the same contract as in
+    /// `movement_costs_with`, and `step_cost` should never return a cost lower
+    /// than `min_step_cost` for the heuristic to stay admissible.
+    /// Returns the path and its total cost, or `None` if `to` cannot be reached.
+    pub(crate) fn movement_path_with<E, F>(
+        from: Vector2i,
+        to: Vector2i,
+        grid_bounds: Rect2i,
+        topology: GridTopology,
+        min_step_cost: i32,
+        ends_movement: E,
+        mut step_cost: F,
+    ) -> Option<(Vec<Vector2i>, i32)>
+    where
+        E: Fn(Vector2i) -> bool,
+        F: FnMut(Vector2i, Vector2i) -> Option<i32>,
+    {
+        let heuristic = |cell: Vector2i| topology.distance(cell, to) * min_step_cost;
+
+        let mut open_queue = PriorityQueue::new();
+        let mut closed_nodes = HashSet::new();
+        let mut came_from = HashMap::<Vector2i, Vector2i>::new();
+        let mut path_costs = HashMap::from([(from, 0_i32)]);
+
+        open_queue.push(from, Reverse(heuristic(from)));
+
+        while let Some((node, _)) = open_queue.pop() {
+            let node_cost = path_costs[&node];
+
+            if node == to {
+                let mut path = vec![to];
+                let mut cell = to;
+
+                while let Some(parent) = came_from.get(&cell) {
+                    path.push(*parent);
+                    cell = *parent;
+                }
+
+                path.reverse();
+
+                return Some((path, node_cost));
+            }
+
+            closed_nodes.insert(node);
+
+            if node != from && ends_movement(node) {
+                continue;
+            }
+
+            for neighbour in Self::get_neighbours(node, grid_bounds, topology) {
+                if closed_nodes.contains(&neighbour) {
+                    continue;
+                }
+
+                if let Some(cost_to_move) = step_cost(node, neighbour) {
+                    let neighbour_cost = node_cost + cost_to_move;
+
+                    if path_costs
+                        .get(&neighbour)
+                        .is_none_or(|known_cost| neighbour_cost < *known_cost)
+                    {
+                        path_costs.insert(neighbour, neighbour_cost);
+                        came_from.insert(neighbour, node);
+                        open_queue.push_increase(
+                            neighbour,
+                            Reverse(neighbour_cost + heuristic(neighbour)),
+                        );
+                    }
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Computes the movement costs of `unit_idx` from its current position,
+    /// using its current movement and movement class.
+    ///
+    /// Returns `None` under the same conditions as
+    /// [`Self::placed_unit_entered_costs`].
+    pub(crate) fn placed_unit_movement_costs(
+        unit_idx: UnitIdx,
+        unit_states: &UnitStates,
+        army_states: &ArmyStates,
+        terrain_grid: &TerrainGrid,
+        zone_of_control: bool,
+    ) -> Option<HashMap<Vector2i, i32>> {
+        let (origin, mut move_costs, rules) = Self::placed_unit_entered_costs(
+            unit_idx,
+            unit_states,
+            army_states,
+            terrain_grid,
+            zone_of_control,
+        )?;
+
+        Self::retain_stopping_cells(&mut move_costs, origin, &rules);
+
+        Some(move_costs)
+    }
+
+    /// Computes the costs of every cell `unit_idx` can enter from its current
+    /// position, using its current movement and movement class, together with
+    /// its position and the movement rules that applied.
+    ///
+    /// Returns `None` if the unit is not placed in the map, does not belong to
+    /// any army or its movement class is not present in `terrain_grid`.
+    pub(crate) fn placed_unit_entered_costs(
+        unit_idx: UnitIdx,
+        unit_states: &UnitStates,
+        army_states: &ArmyStates,
+        terrain_grid: &TerrainGrid,
+        zone_of_control: bool,
+    ) -> Option<(Vector2i, HashMap<Vector2i, i32>, MovementRules)> {
+        let (Some(origin), Some(unit_data)) = (
+            unit_states.unit_idx_to_cell.get(&unit_idx).copied(),
+            unit_states.data_store.get(&unit_idx),
+        ) else {
+            godot_error!("Unit [{}] is not placed in the map!", unit_idx);
+            return None;
+        };
+
+        let unit = unit_data.bind();
+
+        let Some(rules) = MovementRules::for_unit(
+            unit_idx,
+            unit_states,
+            army_states,
+            zone_of_control,
+            terrain_grid,
+        ) else {
+            godot_error!("Unit [{}] does not belong to any army!", unit_idx);
+            return None;
+        };
+
+        let Some(costs) = terrain_grid.costs_for(&unit.movement_class) else {
+            godot_error!(
+                "Movement class [{}] not found in grid!",
+                &unit.movement_class
+            );
+            return None;
+        };
+
+        let move_costs = Self::unit_entered_costs_with(
+            origin,
+            unit.get_current_mov() as i32,
+            terrain_grid,
+            costs,
+            &rules,
+        );
+
+        Some((origin, move_costs, rules))
+    }
+
+    /// Returns the range `unit_idx` uses to interact with `target_idx`, the
+    /// `support_range` for friendly targets and the `attack_range` for any
+    /// other target.
+    ///
+    /// Returns `None` if any of the units is unknown or the unit cannot
+    /// perform the interaction.
+    pub(crate) fn interaction_range(
+        unit_idx: UnitIdx,
+        target_idx: UnitIdx,
+        unit_states: &UnitStates,
+        army_states: &ArmyStates,
+    ) -> Option<Vector2i> {
+        let unit_army_id = unit_states.unit_idx_to_army_id.get(&unit_idx)?;
+        let target_army_id = unit_states.unit_idx_to_army_id.get(&target_idx)?;
+        let unit = unit_states.data_store.get(&unit_idx)?.bind();
+
+        let unit_range = if army_states.are_friendly(unit_army_id, target_army_id) {
+            unit.support_range
+        } else {
+            unit.attack_range
+        };
+
+        (unit_range > Vector2i::ZERO).then_some(unit_range)
+    }
+
+    /// Selects the cells of `move_costs` from which `target_cell` lies within
+    /// `unit_range`, ranked by terrain defense bonus, avoid bonus and path
+    /// cost, the best one first. Ties are broken by cell position so the
+    /// ranking is stable.
+    pub(crate) fn attack_cells_with(
+        move_costs: &HashMap<Vector2i, i32>,
+        target_cell: Vector2i,
+        unit_range: Vector2i,
+        terrain_grid: &TerrainGrid,
+    ) -> Vec<AttackCell> {
+        let mut attack_cells = move_costs
+            .iter()
+            .filter(|(cell, _)| {
+                let distance = terrain_grid.topology.distance(**cell, target_cell);
+                distance >= unit_range.x && distance <= unit_range.y
+            })
+            .map(|(cell, path_cost)| AttackCell {
+                cell: *cell,
+                path_cost: *path_cost,
+                bonus: terrain_grid.bonus_at(*cell),
+            })
+            .collect::<Vec<_>>();
+
+        attack_cells.sort_unstable_by_key(|attack_cell| {
+            (
+                Reverse(attack_cell.bonus.def),
+                Reverse(attack_cell.bonus.avo),
+                attack_cell.path_cost,
+                attack_cell.cell.y,
+                attack_cell.cell.x,
+            )
+        });
+
+        attack_cells
+    }
+
+    /// Computes the cells any unit of `army_id` can attack next turn, as
+    /// described in `compute_danger_zone`.
+    pub(crate) fn danger_zone_of(
+        army_id: &ArmyId,
+        unit_states: &UnitStates,
+        army_states: &ArmyStates,
+        terrain_grid: &TerrainGrid,
+        zone_of_control: bool,
+    ) -> HashSet<Vector2i> {
+        let mut danger_zone = HashSet::new();
+
+        for unit_idx in unit_states.army_units.get(army_id).into_iter().flatten() {
+            let (Some(origin), Some(unit_data)) = (
+                unit_states.unit_idx_to_cell.get(unit_idx).copied(),
+                unit_states.data_store.get(unit_idx),
+            ) else {
+                continue;
+            };
+
+            let unit = unit_data.bind();
+
+            if !unit.can_attack() {
+                continue;
+            }
+
+            let Some(costs) = terrain_grid.costs_for(&unit.movement_class) else {
+                godot_warn!(
+                    "Movement class [{}] of unit [{}] not found in grid!",
+                    &unit.movement_class,
+                    unit_idx
+                );
+                continue;
+            };
+
+            let Some(rules) = MovementRules::for_unit(
+                *unit_idx,
+                unit_states,
+                army_states,
+                zone_of_control,
+                terrain_grid,
+            ) else {
+                continue;
+            };
+
+            danger_zone.extend(Self::unit_danger_zone_with(
+                origin,
+                unit.get_current_mov() as i32,
+                unit.attack_range,
+                terrain_grid,
+                costs,
+                &rules,
+            ));
+        }
+
+        danger_zone
+    }
+
+    /// Computes the cells a unit standing on `origin` can attack next turn,
+    /// moving as in `unit_movement_costs_with` and attacking within
+    /// `attack_range` of every cell it can end its movement on.
+    pub(crate) fn unit_danger_zone_with(
+        origin: Vector2i,
+        eff_move_range: i32,
+        attack_range: Vector2i,
+        terrain_grid: &TerrainGrid,
+        costs: &[i32],
+        rules: &MovementRules,
+    ) -> HashSet<Vector2i> {
+        let move_costs =
+            Self::unit_movement_costs_with(origin, eff_move_range, terrain_grid, costs, rules);
+
+        Self::action_range_with(
+            move_costs.into_keys(),
+            attack_range,
+            terrain_grid.grid_bounds,
+            terrain_grid.topology,
+        )
+    }
+
+    /// Builds the cache key of the current ranges of `unit_idx`.
+    /// Returns `None` if the unit is not placed in the map.
+    fn range_key(
+        unit_idx: UnitIdx,
+        unit_states: &UnitStates,
+        grid_id: InstanceId,
+        zone_of_control: bool,
+    ) -> Option<RangeKey> {
+        let origin = unit_states.unit_idx_to_cell.get(&unit_idx).copied()?;
+        let unit = unit_states.data_store.get(&unit_idx)?.bind();
+
+        Some(RangeKey {
+            grid_id,
+            origin,
+            move_range: unit.get_current_mov() as i32,
+            movement_class: unit.movement_class.clone(),
+            attack_range: unit.attack_range,
+            support_range: unit.support_range,
+            zone_of_control,
+        })
+    }
+
+    /// Converts cached ranges to the format returned to Godot.
+    fn ranges_to_dictionary(maybe_ranges: Option<&CachedRanges>) -> Dictionary {
+        if let Some(ranges) = maybe_ranges {
+            dict! {
+                "movement": ranges.move_costs.keys().copied().collect::<Array<Vector2i>>(),
+                "attack": ranges.attack_cells.iter().copied().collect::<Array<Vector2i>>(),
+                "support": ranges.support_cells.iter().copied().collect::<Array<Vector2i>>(),
+            }
+        } else {
+            dict! {
+                "movement": Array::<Vector2i>::new(),
+                "attack": Array::<Vector2i>::new(),
+                "support": Array::<Vector2i>::new(),
+            }
+        }
+    }
+
+    /// Converts the result of `movement_path_with` to the format returned to Godot.
+    fn path_to_dictionary(maybe_path: Option<(Vec<Vector2i>, i32)>) -> Dictionary {
+        if let Some((path, path_cost)) = maybe_path {
+            dict! {
+                "path": path.into_iter().collect::<Array<Vector2i>>(),
+                "cost": path_cost,
+            }
+        } else {
+            dict! {
+                "path": Array::<Vector2i>::new(),
+                "cost": -1,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod get_neighbours {
+        use super::*;
+
+        #[test]
+        fn get_neighbours_works() {
+            let grid_start = Vector2i::new(0, 0);
+            let grid_end = Vector2i::new(5, 10);
+            let grid_bounds = Rect2i::new(grid_start, grid_end);
+
+            // Neighours should be returned in UP, DOWN, LEFT, RIGHT order.
+            let point_1 = Vector2i::new(1, 1);
+            let neighbours_1 =
+                RustPathfinder::get_neighbours(point_1, grid_bounds, GridTopology::Square4)
+                    .collect::<Vec<_>>();
+            assert_eq!(
+                neighbours_1,
+                vec![
+                    Vector2i::new(1, 0),
+                    Vector2i::new(1, 2),
+                    Vector2i::new(0, 1),
+                    Vector2i::new(2, 1)
+                ]
+            );
+        }
+
+        #[test]
+        fn get_neighbours_returns_empty_iter_when_outside_grid() {
+            let grid_start = Vector2i::new(0, 0);
+            let grid_end = Vector2i::new(5, 10);
+            let grid_bounds = Rect2i::new(grid_start, grid_end);
+
+            // If the cell is completely outside of the grid, no neighours are returned
+            let point_2 = Vector2i::new(-2, -2);
+            let neighbours_2 =
+                RustPathfinder::get_neighbours(point_2, grid_bounds, GridTopology::Square4)
+                    .collect::<Vec<_>>();
+            assert_eq!(neighbours_2, vec![]);
+        }
+
+        #[test]
+        fn get_neighbours_returns_only_neighbours_inside_grid() {
+            let grid_start = Vector2i::new(0, 0);
+            let grid_end = Vector2i::new(5, 10);
+            let grid_bounds = Rect2i::new(grid_start, grid_end);
+
+            // If the cell is at the borders of the grid, only neighbours inside are returned
+            let point_3 = Vector2i::new(0, 0);
+            let neighbours_3 =
+                RustPathfinder::get_neighbours(point_3, grid_bounds, GridTopology::Square4)
+                    .collect::<Vec<_>>();
+            assert_eq!(neighbours_3, vec![Vector2i::new(0, 1), Vector2i::new(1, 0)]);
+            let point_4 = Vector2i::new(3, 0);
+            let neighbours_4 =
+                RustPathfinder::get_neighbours(point_4, grid_bounds, GridTopology::Square4)
+                    .collect::<Vec<_>>();
+            assert_eq!(
+                neighbours_4,
+                vec![
+                    Vector2i::new(3, 1),
+                    Vector2i::new(2, 0),
+                    Vector2i::new(4, 0)
+                ]
+            );
+        }
+    }
+
+    mod movement_costs_with {
+        use super::*;
+
+        #[test]
+        fn movement_costs_with_respects_range_and_costs() {
+            let grid_bounds = Rect2i::new(Vector2i::new(0, 0), Vector2i::new(5, 5));
+            let expensive = Vector2i::new(1, 0);
+
+            let costs = RustPathfinder::movement_costs_with(
+                Vector2i::new(0, 0),
+                2,
+                grid_bounds,
+                GridTopology::Square4,
+                |_| false,
+                |_, neighbour| {
+                    if neighbour == expensive {
+                        Some(3)
+                    } else {
+                        Some(1)
+                    }
+                },
+            );
+
+            assert_eq!(costs.len(), 4);
+            assert_eq!(costs[&Vector2i::new(0, 0)], 0);
+            assert_eq!(costs[&Vector2i::new(0, 1)], 1);
+            assert_eq!(costs[&Vector2i::new(0, 2)], 2);
+            assert_eq!(costs[&Vector2i::new(1, 1)], 2);
+            assert!(!costs.contains_key(&expensive));
+        }
+
+        #[test]
+        fn movement_costs_with_skips_blocked_cells() {
+            let grid_bounds = Rect2i::new(Vector2i::new(0, 0), Vector2i::new(3, 1));
+            let blocked = Vector2i::new(1, 0);
+
+            let costs = RustPathfinder::movement_costs_with(
+                Vector2i::new(0, 0),
+                5,
+                grid_bounds,
+                GridTopology::Square4,
+                |_| false,
+                |_, neighbour| if neighbour == blocked { None } else { Some(1) },
+            );
+
+            assert_eq!(costs.len(), 1);
+        }
+    }
+
+    mod unit_movement_costs_with {
+        use super::*;
+
+        fn open_grid(size: Vector2i) -> TerrainGrid {
+            TerrainGrid::new(Rect2i::new(Vector2i::new(0, 0), size), 0)
+        }
+
+        #[test]
+        fn unit_movement_costs_with_passes_through_allies_only() {
+            let grid = open_grid(Vector2i::new(4, 1));
+            let rules = MovementRules {
+                pass_only: HashSet::from([Vector2i::new(1, 0)]),
+                blocked: HashSet::from([Vector2i::new(3, 0)]),
+                ..Default::default()
+            };
+
+            let costs = RustPathfinder::unit_movement_costs_with(
+                Vector2i::new(0, 0),
+                5,
+                &grid,
+                &[1],
+                &rules,
+            );
+
+            assert_eq!(costs.len(), 2);
+            assert_eq!(costs[&Vector2i::new(2, 0)], 2);
+            assert!(!costs.contains_key(&Vector2i::new(1, 0)));
+            assert!(!costs.contains_key(&Vector2i::new(3, 0)));
+        }
+
+        #[test]
+        fn unit_movement_costs_with_stops_at_zone_of_control() {
+            let grid = open_grid(Vector2i::new(5, 1));
+            let rules = MovementRules {
+                blocked: HashSet::from([Vector2i::new(3, 0)]),
+                zone_of_control: HashSet::from([Vector2i::new(0, 0), Vector2i::new(2, 0)]),
+                ..Default::default()
+            };
+
+            // Starting inside a zone of control does not prevent moving.
+            let costs = RustPathfinder::unit_movement_costs_with(
+                Vector2i::new(0, 0),
+                5,
+                &grid,
+                &[1],
+                &rules,
+            );
+
+            assert_eq!(costs.len(), 3);
+            assert_eq!(costs[&Vector2i::new(2, 0)], 2);
+            assert!(!costs.contains_key(&Vector2i::new(4, 0)));
+        }
+    }
+
+    mod movement_reach_with {
+        use super::*;
+
+        #[test]
+        fn movement_reach_with_covers_allies_moved_through() {
+            let grid = TerrainGrid::new(Rect2i::new(Vector2i::new(0, 0), Vector2i::new(6, 1)), 0);
+            let middle_ally = Vector2i::new(2, 0);
+            let rules = MovementRules {
+                pass_only: HashSet::from([Vector2i::new(1, 0), middle_ally, Vector2i::new(3, 0)]),
+                ..Default::default()
+            };
+
+            let entered = RustPathfinder::unit_entered_costs_with(
+                Vector2i::new(0, 0),
+                5,
+                &grid,
+                &[1],
+                &rules,
+            );
+            let reach = RustPathfinder::movement_reach_with(
+                entered.keys().copied(),
+                grid.grid_bounds,
+                grid.topology,
+            );
+            let stopping = RustPathfinder::unit_movement_costs_with(
+                Vector2i::new(0, 0),
+                5,
+                &grid,
+                &[1],
+                &rules,
+            );
+
+            // Moving the middle ally of the chain must invalidate the ranges,
+            // even if the unit cannot stop on its cell.
+            assert!(!stopping.contains_key(&middle_ally));
+            assert!(reach.contains(&middle_ally));
+            assert!(reach.contains(&Vector2i::new(5, 0)));
+        }
+    }
+
+    mod unit_danger_zone_with {
+        use super::*;
+
+        fn open_grid(size: Vector2i) -> TerrainGrid {
+            TerrainGrid::new(Rect2i::new(Vector2i::new(0, 0), size), 0)
+        }
+
+        #[test]
+        fn unit_danger_zone_with_passes_through_allies() {
+            let grid = open_grid(Vector2i::new(6, 1));
+            let rules = MovementRules {
+                pass_only: HashSet::from([Vector2i::new(1, 0)]),
+                ..Default::default()
+            };
+
+            let danger_zone = RustPathfinder::unit_danger_zone_with(
+                Vector2i::new(0, 0),
+                3,
+                Vector2i::new(1, 1),
+                &grid,
+                &[1],
+                &rules,
+            );
+
+            assert_eq!(
+                danger_zone,
+                HashSet::from([
+                    Vector2i::new(1, 0),
+                    Vector2i::new(2, 0),
+                    Vector2i::new(3, 0),
+                    Vector2i::new(4, 0),
+                ])
+            );
+        }
+
+        #[test]
+        fn unit_danger_zone_with_stops_at_zone_of_control() {
+            let grid = open_grid(Vector2i::new(6, 1));
+            let rules = MovementRules {
+                zone_of_control: HashSet::from([Vector2i::new(2, 0)]),
+                ..Default::default()
+            };
+
+            let danger_zone = RustPathfinder::unit_danger_zone_with(
+                Vector2i::new(0, 0),
+                5,
+                Vector2i::new(1, 1),
+                &grid,
+                &[1],
+                &rules,
+            );
+
+            assert!(danger_zone.contains(&Vector2i::new(3, 0)));
+            assert!(!danger_zone.contains(&Vector2i::new(4, 0)));
+        }
+    }
+
+    mod attack_cells_with {
+        use super::*;
+
+        #[test]
+        fn attack_cells_with_ranks_by_bonus_then_cost() {
+            let grid_bounds = Rect2i::new(Vector2i::new(0, 0), Vector2i::new(5, 5));
+            let mut grid = TerrainGrid::new(grid_bounds, 0);
+            grid.terrain_bonuses = vec![TerrainBonus::default(), TerrainBonus { avo: 20, def: 1 }];
+            // Forest at (3, 2), right of the target.
+            grid.cell_terrains[13] = 1;
+
+            let move_costs = RustPathfinder::movement_costs_with(
+                Vector2i::new(0, 2),
+                4,
+                grid_bounds,
+                GridTopology::Square4,
+                |_| false,
+                |_, _| Some(1),
+            );
+
+            let attack_cells = RustPathfinder::attack_cells_with(
+                &move_costs,
+                Vector2i::new(2, 2),
+                Vector2i::new(1, 1),
+                &grid,
+            );
+
+            let cells = attack_cells
+                .iter()
+                .map(|attack_cell| attack_cell.cell)
+                .collect::<Vec<_>>();
+
+            assert_eq!(
+                cells,
+                vec![
+                    Vector2i::new(3, 2),
+                    Vector2i::new(1, 2),
+                    Vector2i::new(2, 1),
+                    Vector2i::new(2, 3),
+                ]
+            );
+            assert_eq!(attack_cells[0].path_cost, 3);
+            assert_eq!(attack_cells[1].path_cost, 1);
+        }
+    }
+
+    mod flow_field_with {
+        use super::*;
+
+        #[test]
+        fn flow_field_with_uses_closest_goal() {
+            let grid_bounds = Rect2i::new(Vector2i::new(0, 0), Vector2i::new(7, 1));
+            let goals = [Vector2i::new(0, 0), Vector2i::new(6, 0)];
+
+            // Entering (1, 0) is expensive, so (2, 0) is closer to the right goal.
+            let flow_field = RustPathfinder::flow_field_with(
+                goals,
+                grid_bounds,
+                GridTopology::Square4,
+                |_, neighbour| {
+                    if neighbour == Vector2i::new(1, 0) {
+                        Some(5)
+                    } else {
+                        Some(1)
+                    }
+                },
+            );
+
+            assert_eq!(flow_field.distances.len(), 7);
+            assert_eq!(flow_field.distance_at(Vector2i::new(0, 0)), Some(0));
+            assert_eq!(flow_field.distance_at(Vector2i::new(1, 0)), Some(1));
+            assert_eq!(flow_field.distance_at(Vector2i::new(2, 0)), Some(4));
+            assert_eq!(flow_field.distance_at(Vector2i::new(3, 0)), Some(3));
+
+            assert_eq!(
+                flow_field.next_cell(Vector2i::new(2, 0)),
+                Some(Vector2i::new(3, 0))
+            );
+            assert_eq!(
+                flow_field.next_cell(Vector2i::new(1, 0)),
+                Some(Vector2i::new(0, 0))
+            );
+            assert_eq!(flow_field.next_cell(Vector2i::new(6, 0)), None);
+            assert_eq!(
+                flow_field.sorted_by_distance([Vector2i::new(2, 0), Vector2i::new(5, 0)]),
+                vec![Vector2i::new(5, 0), Vector2i::new(2, 0)]
+            );
+        }
+    }
+
+    mod action_range_with {
+        use super::*;
+
+        #[test]
+        fn action_range_with_excludes_cells_below_min_range() {
+            let grid_bounds = Rect2i::new(Vector2i::new(0, 0), Vector2i::new(7, 7));
+            let pivot = Vector2i::new(3, 3);
+
+            let action_range = RustPathfinder::action_range_with(
+                [pivot],
+                Vector2i::new(2, 2),
+                grid_bounds,
+                GridTopology::Square4,
+            );
+
+            assert_eq!(action_range.len(), 8);
+            assert!(!action_range.contains(&pivot));
+            assert!(
+                action_range
+                    .iter()
+                    .all(|cell| RustPathfinder::manhattan_distance(pivot, *cell) == 2)
+            );
+        }
+
+        #[test]
+        fn action_range_with_uses_topology_distance() {
+            let grid_bounds = Rect2i::new(Vector2i::new(0, 0), Vector2i::new(7, 7));
+            let pivot = Vector2i::new(3, 3);
+
+            let hex_range = RustPathfinder::action_range_with(
+                [pivot],
+                Vector2i::new(1, 2),
+                grid_bounds,
+                GridTopology::HexAxial,
+            );
+            let square_range = RustPathfinder::action_range_with(
+                [pivot],
+                Vector2i::new(1, 2),
+                grid_bounds,
+                GridTopology::Square8,
+            );
+
+            assert_eq!(hex_range.len(), 18);
+            assert_eq!(square_range.len(), 24);
+            assert!(!hex_range.contains(&Vector2i::new(5, 5)));
+            assert!(square_range.contains(&Vector2i::new(5, 5)));
+        }
+    }
+
+    mod effect_area_with {
+        use super::*;
+
+        fn area_of(area: AreaShape, caster: Vector2i, aim_cell: Vector2i) -> HashSet<Vector2i> {
+            let grid_bounds = Rect2i::new(Vector2i::new(0, 0), Vector2i::new(9, 9));
+            RustPathfinder::effect_area_with(
+                area,
+                caster,
+                aim_cell,
+                true,
+                grid_bounds,
+                GridTopology::Square4,
+            )
+        }
+
+        #[test]
+        fn effect_area_with_centered_shapes() {
+            let aim_cell = Vector2i::new(4, 4);
+
+            assert_eq!(area_of(AreaShape::Diamond(1), aim_cell, aim_cell).len(), 5);
+            assert_eq!(area_of(AreaShape::Square(1), aim_cell, aim_cell).len(), 9);
+            assert_eq!(area_of(AreaShape::Cross(2), aim_cell, aim_cell).len(), 9);
+            assert!(
+                !area_of(AreaShape::Cross(2), aim_cell, aim_cell).contains(&Vector2i::new(5, 5))
+            );
+            assert_eq!(
+                area_of(AreaShape::Splash(0), aim_cell, aim_cell),
+                HashSet::from([aim_cell])
+            );
+        }
+
+        #[test]
+        fn effect_area_with_cross_skips_diagonals_on_square8() {
+            let grid_bounds = Rect2i::new(Vector2i::new(0, 0), Vector2i::new(9, 9));
+            let aim_cell = Vector2i::new(4, 4);
+
+            let cross = RustPathfinder::effect_area_with(
+                AreaShape::Cross(2),
+                aim_cell,
+                aim_cell,
+                true,
+                grid_bounds,
+                GridTopology::Square8,
+            );
+
+            assert_eq!(cross, area_of(AreaShape::Cross(2), aim_cell, aim_cell));
+            assert!(!cross.contains(&Vector2i::new(5, 5)));
+        }
+
+        #[test]
+        fn effect_area_with_splash_needs_an_occupied_aim_cell() {
+            let grid_bounds = Rect2i::new(Vector2i::new(0, 0), Vector2i::new(9, 9));
+            let aim_cell = Vector2i::new(4, 4);
+
+            let splash_on = |aim_occupied| {
+                RustPathfinder::effect_area_with(
+                    AreaShape::Splash(1),
+                    Vector2i::new(2, 4),
+                    aim_cell,
+                    aim_occupied,
+                    grid_bounds,
+                    GridTopology::Square4,
+                )
+            };
+
+            assert_eq!(splash_on(true).len(), 5);
+            assert!(splash_on(false).is_empty());
+            // Other shapes do not depend on the aim cell being occupied.
+            assert_eq!(
+                RustPathfinder::effect_area_with(
+                    AreaShape::Diamond(1),
+                    Vector2i::new(2, 4),
+                    aim_cell,
+                    false,
+                    grid_bounds,
+                    GridTopology::Square4,
+                )
+                .len(),
+                5
+            );
+        }
+
+        #[test]
+        fn effect_area_with_directional_shapes() {
+            let caster = Vector2i::new(4, 4);
+
+            // The aim cell only picks the direction, the closest one being right.
+            let line = area_of(AreaShape::Line(3), caster, Vector2i::new(8, 5));
+            assert_eq!(
+                line,
+                HashSet::from([
+                    Vector2i::new(5, 4),
+                    Vector2i::new(6, 4),
+                    Vector2i::new(7, 4)
+                ])
+            );
+
+            let cone = area_of(AreaShape::Cone(2), caster, Vector2i::new(4, 0));
+            assert_eq!(
+                cone,
+                HashSet::from([
+                    Vector2i::new(4, 3),
+                    Vector2i::new(4, 2),
+                    Vector2i::new(3, 3),
+                    Vector2i::new(5, 3),
+                ])
+            );
+
+            assert!(area_of(AreaShape::Cone(2), caster, caster).is_empty());
+        }
+
+        #[test]
+        fn effect_area_with_clips_to_grid_bounds() {
+            let corner = Vector2i::new(0, 0);
+
+            assert_eq!(area_of(AreaShape::Diamond(1), corner, corner).len(), 3);
+            assert_eq!(
+                area_of(AreaShape::Line(3), Vector2i::new(1, 0), corner).len(),
+                1
+            );
+        }
+    }
+
+    mod movement_path_with {
+        use super::*;
+
+        #[test]
+        fn movement_path_with_avoids_blocked_cells() {
+            let grid_bounds = Rect2i::new(Vector2i::new(0, 0), Vector2i::new(3, 3));
+            let blocked = Vector2i::new(1, 0);
+
+            let (path, path_cost) = RustPathfinder::movement_path_with(
+                Vector2i::new(0, 0),
+                Vector2i::new(2, 0),
+                grid_bounds,
+                GridTopology::Square4,
+                1,
+                |_| false,
+                |_, neighbour| if neighbour == blocked { None } else { Some(1) },
+            )
+            .unwrap();
+
+            assert_eq!(path.first(), Some(&Vector2i::new(0, 0)));
+            assert_eq!(path.last(), Some(&Vector2i::new(2, 0)));
+            assert_eq!(path.len(), 5);
+            assert_eq!(path_cost, 4);
+            assert!(!path.contains(&blocked));
+        }
+
+        #[test]
+        fn movement_path_with_finds_cheapest_path() {
+            let grid_bounds = Rect2i::new(Vector2i::new(0, 0), Vector2i::new(5, 3));
+
+            // The straight line through row 0 crosses an expensive cell,
+            // going around it through row 1 is cheaper.
+            let (path, path_cost) = RustPathfinder::movement_path_with(
+                Vector2i::new(0, 0),
+                Vector2i::new(4, 0),
+                grid_bounds,
+                GridTopology::Square4,
+                1,
+                |_| false,
+                |_, neighbour| {
+                    if neighbour == Vector2i::new(2, 0) {
+                        Some(5)
+                    } else {
+                        Some(1)
+                    }
+                },
+            )
+            .unwrap();
+
+            assert_eq!(path_cost, 6);
+            assert_eq!(path.len(), 7);
+            assert!(!path.contains(&Vector2i::new(2, 0)));
+            assert!(
+                path.windows(2)
+                    .all(|step| RustPathfinder::manhattan_distance(step[0], step[1]) == 1)
+            );
+        }
+
+        #[test]
+        fn movement_path_with_returns_none_when_unreachable() {
+            let grid_bounds = Rect2i::new(Vector2i::new(0, 0), Vector2i::new(3, 1));
+
+            let maybe_path = RustPathfinder::movement_path_with(
+                Vector2i::new(0, 0),
+                Vector2i::new(2, 0),
+                grid_bounds,
+                GridTopology::Square4,
+                1,
+                |_| false,
+                |_, _| None,
+            );
+
+            assert_eq!(maybe_path, None);
+        }
+    }
+
+    mod manhattan_distance {
+        use super::*;
+
+        #[test]
+        fn manhattan_distance_works() {
+            let point_1 = Vector2i::new(1, 2);
+            let point_2 = Vector2i::new(4, 9);
+            let distance = RustPathfinder::manhattan_distance(point_1, point_2);
+            assert_eq!(distance, 10);
+
+            let point_1 = Vector2i::new(-3, -2);
+            let point_2 = Vector2i::new(-5, -2);
+            let distance = RustPathfinder::manhattan_distance(point_1, point_2);
+            assert_eq!(distance, 2);
+
+            let point_1 = Vector2i::new(0, 0);
+            let point_2 = Vector2i::new(0, 0);
+            let distance = RustPathfinder::manhattan_distance(point_1, point_2);
+            assert_eq!(distance, 0);
+
+            let point_1 = Vector2i::new(-3, 0);
+            let point_2 = Vector2i::new(4, 1);
+            let distance = RustPathfinder::manhattan_distance(point_1, point_2);
+            assert_eq!(distance, 8);
+        }
+    }
+}
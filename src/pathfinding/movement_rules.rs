@@ -0,0 +1,73 @@
+use super::{RustPathfinder, TerrainGrid};
+use crate::game_entities::{army_states::ArmyStates, unit_data::UnitIdx, unit_states::UnitStates};
+
+use godot::prelude::*;
+use std::collections::HashSet;
+
+/// Restrictions the other units on the map impose on the movement of a unit.
+#[derive(Default)]
+pub(crate) struct MovementRules {
+    /// Cells of friendly units, they can be moved through but not stopped on
+    pub(crate) pass_only: HashSet<Vector2i>,
+    /// Cells of non friendly units, they cannot be entered
+    pub(crate) blocked: HashSet<Vector2i>,
+    /// Cells adjacent to hostile units, entering them ends the movement
+    pub(crate) zone_of_control: HashSet<Vector2i>,
+}
+
+impl MovementRules {
+    /// Builds the movement rules of `unit_idx` from the army of every other
+    /// unit in the map, the zone of control cells are only filled if
+    /// `zone_of_control` is **true**.
+    ///
+    /// Returns `None` if `unit_idx` does not belong to any army.
+    pub(crate) fn for_unit(
+        unit_idx: UnitIdx,
+        unit_states: &UnitStates,
+        army_states: &ArmyStates,
+        zone_of_control: bool,
+        terrain_grid: &TerrainGrid,
+    ) -> Option<Self> {
+        let unit_army_id = unit_states.unit_idx_to_army_id.get(&unit_idx)?;
+
+        let mut rules = Self::default();
+
+        for (cell, other_idx) in unit_states.grid_cell_to_idx.iter() {
+            if *other_idx == unit_idx {
+                continue;
+            }
+
+            let Some(other_army_id) = unit_states.unit_idx_to_army_id.get(other_idx) else {
+                continue;
+            };
+
+            if army_states.are_friendly(unit_army_id, other_army_id) {
+                rules.pass_only.insert(*cell);
+            } else {
+                rules.blocked.insert(*cell);
+
+                if zone_of_control && army_states.are_hostile(unit_army_id, other_army_id) {
+                    rules.zone_of_control.extend(RustPathfinder::get_neighbours(
+                        *cell,
+                        terrain_grid.grid_bounds,
+                        terrain_grid.topology,
+                    ));
+                }
+            }
+        }
+
+        Some(rules)
+    }
+
+    pub(crate) fn can_enter(&self, cell: Vector2i) -> bool {
+        !self.blocked.contains(&cell)
+    }
+
+    pub(crate) fn can_stop_at(&self, cell: Vector2i) -> bool {
+        !self.blocked.contains(&cell) && !self.pass_only.contains(&cell)
+    }
+
+    pub(crate) fn ends_movement_at(&self, cell: Vector2i) -> bool {
+        self.zone_of_control.contains(&cell)
+    }
+}
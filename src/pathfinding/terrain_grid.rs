@@ -0,0 +1,455 @@
+use super::{GridTopology, RustPathfinder};
+use crate::database::{DbConnector, movement_class::MovementClassId, terrain::TerrainId};
+
+use godot::prelude::*;
+use std::collections::{HashMap, HashSet};
+
+pub(crate) type TerrainIdx = u8;
+pub(crate) type MovementClass = MovementClassId;
+
+/// Movement costs lower or equal than this value make a terrain impassable.
+pub(crate) const IMPASSABLE_COST: i32 = 0;
+
+/// Default cost of a diagonal step as a percentage of the terrain cost.
+pub(crate) const DEFAULT_DIAGONAL_COST_PERCENT: i32 = 150;
+
+/// Combat bonuses granted to units standing on a terrain.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub(crate) struct TerrainBonus {
+    pub(crate) avo: i32,
+    pub(crate) def: i32,
+}
+
+/// Rust side representation of a battle map, holds the terrain type of every
+/// cell inside `grid_bounds` together with the movement cost tables of each
+/// movement class, so pathfinding can run without calling back into GDScript.
+#[derive(GodotClass)]
+#[class(no_init, base=RefCounted)]
+pub(crate) struct TerrainGrid {
+    pub(crate) grid_bounds: Rect2i,
+    pub(crate) topology: GridTopology,
+    /// Cost of a diagonal step as a percentage of the terrain cost,
+    /// only used by the `Square8` topology
+    pub(crate) diagonal_cost_percent: i32,
+    /// Terrain index of every cell in row-major order
+    pub(crate) cell_terrains: Vec<TerrainIdx>,
+    /// Database terrain identifier of each terrain index, only
+    /// filled when the cost tables are loaded from the database
+    pub(crate) terrain_palette: Vec<TerrainId>,
+    pub(crate) solid_cells: HashSet<Vector2i>,
+    /// Combat bonuses of each terrain index, missing indexes have no bonus
+    pub(crate) terrain_bonuses: Vec<TerrainBonus>,
+    /// Table of movement class to the cost of entering each terrain index
+    pub(crate) movement_costs: HashMap<MovementClass, Vec<i32>>,
+    /// Incremented on every change that affects movement
+    pub(crate) revision: u64,
+    /// Revision of the last change that affected the whole grid
+    pub(crate) global_revision: u64,
+    /// Revision of the last change of every modified cell
+    pub(crate) cell_revisions: HashMap<Vector2i, u64>,
+}
+
+impl TerrainGrid {
+    pub(crate) fn new(grid_bounds: Rect2i, default_terrain: TerrainIdx) -> Self {
+        Self {
+            grid_bounds,
+            topology: GridTopology::default(),
+            diagonal_cost_percent: DEFAULT_DIAGONAL_COST_PERCENT,
+            cell_terrains: vec![default_terrain; grid_bounds.area().max(0) as usize],
+            terrain_palette: Vec::new(),
+            solid_cells: HashSet::new(),
+            terrain_bonuses: Vec::new(),
+            movement_costs: HashMap::new(),
+            revision: 0,
+            global_revision: 0,
+            cell_revisions: HashMap::new(),
+        }
+    }
+
+    /// Records a change affecting movement, in `cell` if given
+    /// or in the whole grid otherwise.
+    fn mark_changed(&mut self, maybe_cell: Option<Vector2i>) {
+        self.revision += 1;
+
+        if let Some(cell) = maybe_cell {
+            self.cell_revisions.insert(cell, self.revision);
+        } else {
+            self.global_revision = self.revision;
+            self.cell_revisions.clear();
+        }
+    }
+
+    /// Returns **true** if the whole grid or any of `cells` changed after
+    /// `revision`.
+    pub(crate) fn changed_since(&self, revision: u64, cells: &HashSet<Vector2i>) -> bool {
+        if self.global_revision > revision {
+            return true;
+        }
+
+        if self.cell_revisions.len() < cells.len() {
+            self.cell_revisions
+                .iter()
+                .any(|(cell, cell_revision)| *cell_revision > revision && cells.contains(cell))
+        } else {
+            cells.iter().any(|cell| {
+                self.cell_revisions
+                    .get(cell)
+                    .is_some_and(|cell_revision| *cell_revision > revision)
+            })
+        }
+    }
+
+    fn cell_offset(&self, cell: Vector2i) -> Option<usize> {
+        if self.grid_bounds.contains_point(cell) {
+            let local = cell - self.grid_bounds.position;
+            Some((local.y * self.grid_bounds.size.x + local.x) as usize)
+        } else {
+            None
+        }
+    }
+
+    pub(crate) fn terrain_at(&self, cell: Vector2i) -> Option<TerrainIdx> {
+        self.cell_offset(cell)
+            .and_then(|offset| self.cell_terrains.get(offset))
+            .copied()
+    }
+
+    pub(crate) fn bonus_at(&self, cell: Vector2i) -> TerrainBonus {
+        self.terrain_at(cell)
+            .and_then(|terrain| self.terrain_bonuses.get(terrain as usize))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn costs_for(&self, movement_class: &MovementClass) -> Option<&[i32]> {
+        self.movement_costs.get(movement_class).map(Vec::as_slice)
+    }
+
+    /// Returns the lowest passable cost of the `costs` table,
+    /// used to scale the path search heuristic.
+    pub(crate) fn min_step_cost(costs: &[i32]) -> i32 {
+        costs
+            .iter()
+            .copied()
+            .filter(|cost| *cost > IMPASSABLE_COST)
+            .min()
+            .unwrap_or(1)
+    }
+
+    /// Returns the cost of entering `cell` using the `costs` table.
+    /// Returns `None` if the cell is solid, outside the grid or impassable.
+    pub(crate) fn step_cost(&self, costs: &[i32], cell: Vector2i) -> Option<i32> {
+        if self.solid_cells.contains(&cell) {
+            return None;
+        }
+
+        self.terrain_at(cell)
+            .and_then(|terrain| costs.get(terrain as usize))
+            .copied()
+            .filter(|cost| *cost > IMPASSABLE_COST)
+    }
+
+    /// Returns the cost of moving from `cell` into its adjacent cell
+    /// `neighbour`, scaling the cost of diagonal steps.
+    /// Returns `None` if `neighbour` cannot be entered.
+    pub(crate) fn move_cost(
+        &self,
+        costs: &[i32],
+        cell: Vector2i,
+        neighbour: Vector2i,
+    ) -> Option<i32> {
+        let step_cost = self.step_cost(costs, neighbour)?;
+
+        if self.topology.is_diagonal_step(cell, neighbour) {
+            Some((step_cost * self.diagonal_cost_percent + 99) / 100)
+        } else {
+            Some(step_cost)
+        }
+    }
+}
+
+#[godot_api]
+impl TerrainGrid {
+    /// Creates a new grid covering `grid_bounds` where every cell has
+    /// the `default_terrain` terrain index.
+    ///
+    /// Will return **null** if `grid_bounds` has no area.
+    #[func]
+    fn with_bounds(grid_bounds: Rect2i, default_terrain: TerrainIdx) -> Option<Gd<Self>> {
+        if !grid_bounds.has_area() {
+            godot_error!("TerrainGrid bounds {} have no area!", grid_bounds);
+            return None;
+        }
+
+        Some(Gd::from_object(Self::new(grid_bounds, default_terrain)))
+    }
+
+    #[func]
+    fn set_topology(&mut self, topology: GridTopology) {
+        self.topology = topology;
+        self.mark_changed(None);
+    }
+
+    #[func]
+    fn get_topology(&self) -> GridTopology {
+        self.topology
+    }
+
+    /// Sets the cost of a diagonal step as a percentage of the terrain cost.
+    /// Returns **false** if `percent` is lower than 100, as diagonal steps
+    /// cannot be cheaper than orthogonal ones.
+    #[func]
+    fn set_diagonal_cost_percent(&mut self, percent: i32) -> bool {
+        if percent < 100 {
+            godot_error!(
+                "Diagonal cost percent {} cannot be lower than 100!",
+                percent
+            );
+            return false;
+        }
+
+        self.diagonal_cost_percent = percent;
+        self.mark_changed(None);
+
+        true
+    }
+
+    /// Sets the terrain index of `cell`.
+    /// Returns **false** if `cell` is outside the grid bounds.
+    #[func]
+    fn set_terrain_at(&mut self, cell: Vector2i, terrain: TerrainIdx) -> bool {
+        if let Some(offset) = self.cell_offset(cell) {
+            if self.cell_terrains[offset] != terrain {
+                self.cell_terrains[offset] = terrain;
+                self.mark_changed(Some(cell));
+            }
+            true
+        } else {
+            godot_error!("Cell {} is outside of the grid bounds!", cell);
+            false
+        }
+    }
+
+    /// Tries to get the terrain index of `cell`.
+    /// Returns -1 if `cell` is outside the grid bounds.
+    #[func]
+    fn get_terrain_at(&self, cell: Vector2i) -> i32 {
+        self.terrain_at(cell)
+            .map(|terrain| terrain as i32)
+            .unwrap_or(-1)
+    }
+
+    #[func]
+    fn set_solid_at(&mut self, cell: Vector2i, is_solid: bool) {
+        let changed = if is_solid {
+            self.solid_cells.insert(cell)
+        } else {
+            self.solid_cells.remove(&cell)
+        };
+
+        if changed {
+            self.mark_changed(Some(cell));
+        }
+    }
+
+    #[func]
+    fn is_solid_at(&self, cell: Vector2i) -> bool {
+        self.solid_cells.contains(&cell)
+    }
+
+    #[func]
+    fn get_grid_bounds(&self) -> Rect2i {
+        self.grid_bounds
+    }
+
+    /// Returns the cells adjacent to `cell` inside the grid bounds.
+    #[func]
+    fn get_neighbours(&self, cell: Vector2i) -> Array<Vector2i> {
+        RustPathfinder::get_neighbours(cell, self.grid_bounds, self.topology).collect()
+    }
+
+    /// Returns the number of steps between `from` and `to` in the grid topology.
+    #[func]
+    fn get_distance(&self, from: Vector2i, to: Vector2i) -> i32 {
+        self.topology.distance(from, to)
+    }
+
+    /// Returns the solid cells with the format `{<cell>: true}`, so the grid
+    /// can also be used as `grid_data` for the `Callable` based pathfinding.
+    #[func]
+    fn get_solid_nodes(&self) -> Dictionary {
+        self.solid_cells.iter().map(|cell| (*cell, true)).collect()
+    }
+
+    /// Sets the combat bonuses of units standing on the `terrain` terrain index.
+    #[func]
+    fn set_terrain_bonus(&mut self, terrain: TerrainIdx, avo_bonus: i32, def_bonus: i32) {
+        let terrain = terrain as usize;
+
+        if self.terrain_bonuses.len() <= terrain {
+            self.terrain_bonuses
+                .resize(terrain + 1, TerrainBonus::default());
+        }
+
+        self.terrain_bonuses[terrain] = TerrainBonus {
+            avo: avo_bonus,
+            def: def_bonus,
+        };
+    }
+
+    /// Sets the movement costs of `movement_class`, where `costs[i]` is the
+    /// cost of entering a cell with terrain index `i`.
+    ///
+    /// Costs lower or equal than zero mark the terrain as impassable, terrain
+    /// indexes not present in `costs` are also impassable.
+    #[func]
+    fn set_movement_costs(&mut self, movement_class: MovementClass, costs: PackedInt32Array) {
+        self.movement_costs
+            .insert(movement_class, costs.as_slice().to_vec());
+        self.mark_changed(None);
+    }
+
+    /// Replaces the movement cost tables with the movement classes found in
+    /// the database, where `terrain_palette[i]` is the database terrain
+    /// identifier of the terrain index `i`. Terrain bonuses are also
+    /// replaced with the ones of the palette terrains.
+    ///
+    /// Returns **false** if any terrain of the palette is not in the database.
+    #[func]
+    fn load_movement_classes(
+        &mut self,
+        db: Gd<DbConnector>,
+        terrain_palette: Array<TerrainId>,
+    ) -> bool {
+        let db_link = db.bind();
+        let palette = terrain_palette.iter_shared().collect::<Vec<_>>();
+
+        let mut palette_move_costs = Vec::with_capacity(palette.len());
+        let mut palette_bonuses = Vec::with_capacity(palette.len());
+        for terrain_id in palette.iter() {
+            if let Some(terrain_entry) = db_link.terrain.get(terrain_id) {
+                palette_move_costs.push(terrain_entry.move_cost);
+                palette_bonuses.push(TerrainBonus {
+                    avo: terrain_entry.avo_bonus as i32,
+                    def: terrain_entry.def_bonus as i32,
+                });
+            } else {
+                godot_error!("Terrain [{}] not found in database!", terrain_id);
+                return false;
+            }
+        }
+
+        self.movement_costs = db_link
+            .movement_classes
+            .iter()
+            .map(|(class_id, class_entry)| {
+                let costs = palette
+                    .iter()
+                    .zip(palette_move_costs.iter())
+                    .map(|(terrain_id, move_cost)| {
+                        if class_entry.impassable.contains(terrain_id) {
+                            IMPASSABLE_COST
+                        } else {
+                            *class_entry.costs.get(terrain_id).unwrap_or(move_cost) as i32
+                        }
+                    })
+                    .collect();
+
+                (class_id.clone(), costs)
+            })
+            .collect();
+        self.terrain_bonuses = palette_bonuses;
+        self.terrain_palette = palette;
+        self.mark_changed(None);
+
+        true
+    }
+
+    /// Returns the revision of the grid, which increases on every change
+    /// that affects movement.
+    #[func]
+    fn get_revision(&self) -> u64 {
+        self.revision
+    }
+
+    #[func]
+    fn has_movement_class(&self, movement_class: MovementClass) -> bool {
+        self.movement_costs.contains_key(&movement_class)
+    }
+
+    /// Tries to get the cost of entering `cell` for `movement_class`.
+    /// Returns -1 if the cell cannot be entered.
+    #[func]
+    fn get_movement_cost(&self, movement_class: MovementClass, cell: Vector2i) -> i32 {
+        self.costs_for(&movement_class)
+            .and_then(|costs| self.step_cost(costs, cell))
+            .unwrap_or(-1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_grid() -> TerrainGrid {
+        TerrainGrid::new(Rect2i::new(Vector2i::new(0, 0), Vector2i::new(4, 3)), 0)
+    }
+
+    #[test]
+    fn terrain_at_uses_row_major_offsets() {
+        let mut grid = test_grid();
+        grid.cell_terrains[6] = 2;
+
+        assert_eq!(grid.terrain_at(Vector2i::new(2, 1)), Some(2));
+        assert_eq!(grid.terrain_at(Vector2i::new(1, 2)), Some(0));
+        assert_eq!(grid.terrain_at(Vector2i::new(4, 0)), None);
+        assert_eq!(grid.terrain_at(Vector2i::new(-1, 0)), None);
+    }
+
+    #[test]
+    fn step_cost_skips_solid_and_impassable_cells() {
+        let mut grid = test_grid();
+        grid.cell_terrains[1] = 1;
+        grid.cell_terrains[2] = 2;
+        grid.solid_cells.insert(Vector2i::new(3, 0));
+
+        let costs = [1, IMPASSABLE_COST];
+
+        assert_eq!(grid.step_cost(&costs, Vector2i::new(0, 0)), Some(1));
+        assert_eq!(grid.step_cost(&costs, Vector2i::new(1, 0)), None);
+        assert_eq!(grid.step_cost(&costs, Vector2i::new(2, 0)), None);
+        assert_eq!(grid.step_cost(&costs, Vector2i::new(3, 0)), None);
+    }
+
+    #[test]
+    fn changed_since_tracks_cell_and_grid_changes() {
+        let mut grid = test_grid();
+        let reach = HashSet::from([Vector2i::new(0, 0), Vector2i::new(1, 0)]);
+
+        grid.mark_changed(Some(Vector2i::new(3, 2)));
+        let revision = grid.revision;
+        assert!(!grid.changed_since(revision, &reach));
+
+        grid.mark_changed(Some(Vector2i::new(2, 2)));
+        assert!(!grid.changed_since(revision, &reach));
+
+        grid.mark_changed(Some(Vector2i::new(1, 0)));
+        assert!(grid.changed_since(revision, &reach));
+
+        let revision = grid.revision;
+        grid.mark_changed(None);
+        assert!(grid.changed_since(revision, &reach));
+    }
+
+    #[test]
+    fn move_cost_scales_diagonal_steps() {
+        let mut grid = test_grid();
+        grid.topology = GridTopology::Square8;
+
+        let costs = [2];
+        let origin = Vector2i::new(1, 1);
+
+        assert_eq!(grid.move_cost(&costs, origin, Vector2i::new(2, 1)), Some(2));
+        assert_eq!(grid.move_cost(&costs, origin, Vector2i::new(2, 2)), Some(3));
+    }
+}
@@ -0,0 +1,200 @@
+use godot::prelude::*;
+
+const SQUARE_4_DIRECTIONS: [Vector2i; 4] = [
+    Vector2i::UP,
+    Vector2i::DOWN,
+    Vector2i::LEFT,
+    Vector2i::RIGHT,
+];
+
+const SQUARE_8_DIRECTIONS: [Vector2i; 8] = [
+    Vector2i::UP,
+    Vector2i::DOWN,
+    Vector2i::LEFT,
+    Vector2i::RIGHT,
+    Vector2i::new(-1, -1),
+    Vector2i::new(1, -1),
+    Vector2i::new(-1, 1),
+    Vector2i::new(1, 1),
+];
+
+const HEX_ODD_R_EVEN_ROW_DIRECTIONS: [Vector2i; 6] = [
+    Vector2i::new(-1, -1),
+    Vector2i::new(0, -1),
+    Vector2i::new(-1, 1),
+    Vector2i::new(0, 1),
+    Vector2i::LEFT,
+    Vector2i::RIGHT,
+];
+
+const HEX_ODD_R_ODD_ROW_DIRECTIONS: [Vector2i; 6] = [
+    Vector2i::new(0, -1),
+    Vector2i::new(1, -1),
+    Vector2i::new(0, 1),
+    Vector2i::new(1, 1),
+    Vector2i::LEFT,
+    Vector2i::RIGHT,
+];
+
+const HEX_AXIAL_DIRECTIONS: [Vector2i; 6] = [
+    Vector2i::UP,
+    Vector2i::new(1, -1),
+    Vector2i::DOWN,
+    Vector2i::new(-1, 1),
+    Vector2i::LEFT,
+    Vector2i::RIGHT,
+];
+
+/// Layout of the cells of a map, defines which cells are adjacent to
+/// each other and how distances between cells are measured.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum GridTopology {
+    /// Square cells connected orthogonally, uses the manhattan distance
+    #[default]
+    Square4 = 0,
+    /// Square cells connected orthogonally and diagonally, uses the
+    /// chebyshev distance
+    Square8 = 1,
+    /// Pointy top hexagons in offset coordinates where odd rows are
+    /// shifted right by half a cell
+    HexOddR = 2,
+    /// Pointy top hexagons in axial `(q, r)` coordinates
+    HexAxial = 3,
+}
+
+impl GodotConvert for GridTopology {
+    type Via = u8;
+}
+
+impl ToGodot for GridTopology {
+    type ToVia<'v> = u8;
+
+    fn to_godot(&self) -> Self::ToVia<'_> {
+        *self as u8
+    }
+}
+
+impl FromGodot for GridTopology {
+    fn try_from_godot(via: Self::Via) -> Result<Self, ConvertError> {
+        match via {
+            0 => Ok(Self::Square4),
+            1 => Ok(Self::Square8),
+            2 => Ok(Self::HexOddR),
+            3 => Ok(Self::HexAxial),
+            other => Err(ConvertError::new(format!(
+                "Unknown GridTopology value [{}]!",
+                other
+            ))),
+        }
+    }
+}
+
+impl GridTopology {
+    /// Returns the offsets from `cell` to each of its adjacent cells.
+    pub(crate) fn directions(self, cell: Vector2i) -> &'static [Vector2i] {
+        match self {
+            GridTopology::Square4 => &SQUARE_4_DIRECTIONS,
+            GridTopology::Square8 => &SQUARE_8_DIRECTIONS,
+            GridTopology::HexOddR if cell.y & 1 == 0 => &HEX_ODD_R_EVEN_ROW_DIRECTIONS,
+            GridTopology::HexOddR => &HEX_ODD_R_ODD_ROW_DIRECTIONS,
+            GridTopology::HexAxial => &HEX_AXIAL_DIRECTIONS,
+        }
+    }
+
+    /// Returns the minimum number of steps between `from` and `to`.
+    pub(crate) fn distance(self, from: Vector2i, to: Vector2i) -> i32 {
+        match self {
+            GridTopology::Square4 => (from.x - to.x).abs() + (from.y - to.y).abs(),
+            GridTopology::Square8 => (from.x - to.x).abs().max((from.y - to.y).abs()),
+            GridTopology::HexOddR => {
+                Self::axial_distance(Self::odd_r_to_axial(from), Self::odd_r_to_axial(to))
+            }
+            GridTopology::HexAxial => Self::axial_distance(from, to),
+        }
+    }
+
+    /// Returns **true** if moving between the adjacent cells `from` and `to`
+    /// is a diagonal step.
+    pub(crate) fn is_diagonal_step(self, from: Vector2i, to: Vector2i) -> bool {
+        self == GridTopology::Square8 && from.x != to.x && from.y != to.y
+    }
+
+    /// Returns the position of the center of `cell` in a space where adjacent
+    /// cells are one unit apart, used to compare directions between cells.
+    pub(crate) fn world_position(self, cell: Vector2i) -> Vector2 {
+        const HEX_ROW_HEIGHT: f32 = 0.866_025_4;
+
+        match self {
+            GridTopology::Square4 | GridTopology::Square8 => {
+                Vector2::new(cell.x as f32, cell.y as f32)
+            }
+            GridTopology::HexOddR => Vector2::new(
+                cell.x as f32 + 0.5 * (cell.y & 1) as f32,
+                cell.y as f32 * HEX_ROW_HEIGHT,
+            ),
+            GridTopology::HexAxial => Vector2::new(
+                cell.x as f32 + 0.5 * cell.y as f32,
+                cell.y as f32 * HEX_ROW_HEIGHT,
+            ),
+        }
+    }
+
+    /// Returns half of the opening angle, in radians, of cone shaped areas.
+    pub(crate) fn cone_half_angle(self) -> f32 {
+        match self {
+            GridTopology::Square4 | GridTopology::Square8 => std::f32::consts::FRAC_PI_4,
+            GridTopology::HexOddR | GridTopology::HexAxial => std::f32::consts::FRAC_PI_6,
+        }
+    }
+
+    fn odd_r_to_axial(cell: Vector2i) -> Vector2i {
+        Vector2i::new(cell.x - (cell.y - (cell.y & 1)) / 2, cell.y)
+    }
+
+    fn axial_distance(from: Vector2i, to: Vector2i) -> i32 {
+        let dq = from.x - to.x;
+        let dr = from.y - to.y;
+
+        (dq.abs() + dr.abs() + (dq + dr).abs()) / 2
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn neighbours_are_at_distance_one() {
+        let topologies = [
+            GridTopology::Square4,
+            GridTopology::Square8,
+            GridTopology::HexOddR,
+            GridTopology::HexAxial,
+        ];
+
+        for topology in topologies {
+            for cell in [
+                Vector2i::new(3, 4),
+                Vector2i::new(3, 5),
+                Vector2i::new(-2, -1),
+            ] {
+                for direction in topology.directions(cell) {
+                    assert_eq!(topology.distance(cell, cell + *direction), 1);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn hex_distances_work() {
+        let odd_r = GridTopology::HexOddR;
+        assert_eq!(odd_r.distance(Vector2i::new(0, 0), Vector2i::new(0, 2)), 2);
+        assert_eq!(odd_r.distance(Vector2i::new(0, 0), Vector2i::new(0, 1)), 1);
+        assert_eq!(odd_r.distance(Vector2i::new(0, 0), Vector2i::new(1, 1)), 2);
+        assert_eq!(odd_r.distance(Vector2i::new(0, 0), Vector2i::new(3, 1)), 4);
+
+        let axial = GridTopology::HexAxial;
+        assert_eq!(axial.distance(Vector2i::new(0, 0), Vector2i::new(2, -2)), 2);
+        assert_eq!(axial.distance(Vector2i::new(0, 0), Vector2i::new(2, 2)), 4);
+    }
+}
@@ -1,7 +1,10 @@
 use super::dialogue::DialogueState;
 use crate::{
     database::{
-        army::ArmyId, chapter::Vector2u8, effect::EffectId, personality::PersonalityId,
+        army::ArmyId,
+        chapter::{DefendRadii, Vector2u8},
+        effect::EffectId,
+        personality::PersonalityId,
         unit::UnitId,
     },
     game_entities::unit_data::UnitIdx,
@@ -173,6 +176,12 @@ pub(super) struct BattleState {
     /// personality entries, they will all share the
     /// same defend cell
     defend_cells: HashMap<UnitIdx, Vector2u8>,
+    /// Table of unit idx to the radii guarded around its
+    /// defend cell, shared by all its MovementBehaviour::Defend
+    /// personality entries like the defend cell
+    /// Units without an entry use the default radii
+    #[serde(default)]
+    defend_radii: HashMap<UnitIdx, DefendRadii>,
 }
 
 impl GodotConvert for BattleState {
@@ -239,6 +248,13 @@ impl ToGodot for BattleState {
                 .collect::<Dictionary>()
         });
 
+        dict.set("defend_radii", {
+            self.defend_radii
+                .iter()
+                .map(|(idx, radii)| (idx.to_variant(), radii.to_variant()))
+                .collect::<Dictionary>()
+        });
+
         dict
     }
 }
@@ -292,6 +308,20 @@ impl FromGodot for BattleState {
                 .iter_shared()
                 .map(|(idx, cell)| (UnitIdx::from_variant(&idx), Vector2u8::from_variant(&cell)))
                 .collect(),
+            defend_radii: via
+                .get("defend_radii")
+                .map(|radii| {
+                    Dictionary::from_variant(&radii)
+                        .iter_shared()
+                        .map(|(idx, radii)| {
+                            (
+                                UnitIdx::from_variant(&idx),
+                                DefendRadii::from_variant(&radii),
+                            )
+                        })
+                        .collect()
+                })
+                .unwrap_or_default(),
         }
     }
 }
@@ -1,5 +1,5 @@
 use crate::{
-    database::{chapter::ChapterKey, unit::UnitId},
+    database::{chapter::ChapterKey, difficulty::DifficultyId, unit::UnitId},
     traits::GetVariantOr,
 };
 
@@ -159,6 +159,9 @@ pub(super) struct GameState {
     story_flags: flags::StoryFlags,
     character_flags: flags::CharacterFlags,
     pub(super) chapter_state: ChapterSaveState,
+    /// Difficulty profile selected at chapter start, empty if none
+    #[serde(default)]
+    difficulty: DifficultyId,
 }
 
 impl GameState {
@@ -183,6 +186,10 @@ impl GameState {
             self.chapter_state
                 .update_data(Dictionary::from_variant(&data));
         }
+
+        if let Some(data) = update_data.get("difficulty") {
+            self.difficulty = DifficultyId::from_variant(&data);
+        }
     }
 }
 
@@ -205,6 +212,7 @@ impl ToGodot for GameState {
                 .collect::<Dictionary>(),
         );
         state_dict.set("chapter_state", self.chapter_state.to_godot());
+        state_dict.set("difficulty", self.difficulty.clone());
 
         state_dict
     }
@@ -234,6 +242,9 @@ impl FromGodot for GameState {
             })
             .collect(),
             chapter_state: ChapterSaveState::from_variant(&via.at("chapter_state")),
+            difficulty: DifficultyId::from_variant(
+                &via.get_or("difficulty", DifficultyId::default()),
+            ),
         }
     }
 }
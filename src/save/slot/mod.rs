@@ -1,3 +1,5 @@
+use crate::game_entities::battle_rng::HitAccumulators;
+
 use chrono::TimeZone;
 use godot::prelude::*;
 use rust_extensions_macros::ToGodotDictionary;
@@ -46,6 +48,8 @@ pub(super) struct SlotPoint {
     game_state: game_state::GameState,
     player_barracks: barracks_state::BarracksState,
     rand_state: i64,
+    #[serde(default)]
+    hit_accumulated: HitAccumulators,
 }
 
 impl SlotPoint {
@@ -62,6 +66,10 @@ impl SlotPoint {
         if let Some(data) = update_data.get("rand_state") {
             self.rand_state = i64::from_variant(&data);
         }
+
+        if let Some(data) = update_data.get("hit_accumulated") {
+            self.hit_accumulated = HitAccumulators::from_variant(&data);
+        }
     }
 }
 
@@ -77,6 +85,10 @@ impl FromGodot for SlotPoint {
                 &via.at("player_barracks"),
             ),
             rand_state: i64::from_variant(&via.at("rand_state")),
+            hit_accumulated: via
+                .get("hit_accumulated")
+                .map(|data| HitAccumulators::from_variant(&data))
+                .unwrap_or_default(),
         }
     }
 }